@@ -0,0 +1,212 @@
+use crate::models::StyledSpan;
+
+// Names of the 8 basic colors, the bright ones get a bright_ prefix
+const COLOR_NAMES: [&str; 8] = [
+  "black",
+  "red",
+  "green",
+  "yellow",
+  "blue",
+  "magenta",
+  "cyan",
+  "white",
+];
+
+// The style set by the SGR sequences seen so far
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+  color: Option<String>,
+  background: Option<String>,
+  bold: bool,
+  italic: bool,
+  underline: bool,
+}
+
+/// Split a line with ANSI escape sequences into its plain text and styled spans.
+/// Spans are only returned if the line had any escape sequences
+pub fn parse_ansi(line: &str) -> (String, Vec<StyledSpan>) {
+  if !line.contains('\u{1b}') {
+    return (line.to_string(), Vec::new());
+  }
+
+  let mut plain = String::with_capacity(line.len());
+  let mut spans: Vec<StyledSpan> = Vec::new();
+  let mut style = Style::default();
+  let mut chars = line.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    if c != '\u{1b}' {
+      plain.push(c);
+      push_char(&mut spans, &style, c);
+      continue;
+    }
+
+    match chars.next() {
+      // CSI, ESC [ params final byte
+      Some('[') => {
+        let mut params = String::new();
+        let mut final_byte = None;
+        for c in chars.by_ref() {
+          if ('\u{40}'..='\u{7e}').contains(&c) {
+            final_byte = Some(c);
+            break;
+          }
+          params.push(c);
+        }
+        // Anything but SGR (cursor movement, clearing the line...) is dropped
+        if final_byte == Some('m') {
+          apply_sgr(&mut style, &params);
+        }
+      }
+      // OSC, ends with BEL or ESC \
+      Some(']') => {
+        while let Some(c) = chars.next() {
+          if c == '\u{7}' {
+            break;
+          }
+          if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+            chars.next();
+            break;
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  (plain, spans)
+}
+
+/// Remove every ANSI escape sequence from a line
+pub fn strip_ansi(line: &str) -> String {
+  parse_ansi(line).0
+}
+
+fn push_char(spans: &mut Vec<StyledSpan>, style: &Style, c: char) {
+  if let Some(last) = spans.last_mut() {
+    let same_style =
+      last.color == style.color &&
+      last.background == style.background &&
+      last.bold == style.bold &&
+      last.italic == style.italic &&
+      last.underline == style.underline;
+    if same_style {
+      last.text.push(c);
+      return;
+    }
+  }
+
+  spans.push(StyledSpan {
+    text: c.to_string(),
+    color: style.color.clone(),
+    background: style.background.clone(),
+    bold: style.bold,
+    italic: style.italic,
+    underline: style.underline,
+  });
+}
+
+fn apply_sgr(style: &mut Style, params: &str) {
+  // ESC[m is the same as ESC[0m
+  let codes = params
+    .split(';')
+    .map(|p| p.parse::<u32>().unwrap_or(0))
+    .collect::<Vec<_>>();
+
+  let mut i = 0;
+  while i < codes.len() {
+    match codes[i] {
+      0 => {
+        *style = Style::default();
+      }
+      1 => {
+        style.bold = true;
+      }
+      3 => {
+        style.italic = true;
+      }
+      4 => {
+        style.underline = true;
+      }
+      22 => {
+        style.bold = false;
+      }
+      23 => {
+        style.italic = false;
+      }
+      24 => {
+        style.underline = false;
+      }
+      code @ 30..=37 => {
+        style.color = Some(COLOR_NAMES[(code - 30) as usize].to_string());
+      }
+      code @ 90..=97 => {
+        style.color = Some(format!("bright_{}", COLOR_NAMES[(code - 90) as usize]));
+      }
+      39 => {
+        style.color = None;
+      }
+      code @ 40..=47 => {
+        style.background = Some(COLOR_NAMES[(code - 40) as usize].to_string());
+      }
+      code @ 100..=107 => {
+        style.background = Some(
+          format!("bright_{}", COLOR_NAMES[(code - 100) as usize])
+        );
+      }
+      49 => {
+        style.background = None;
+      }
+      code @ (38 | 48) => {
+        let (color, used) = extended_color(&codes[i + 1..]);
+        i += used;
+        if code == 38 {
+          style.color = color;
+        } else {
+          style.background = color;
+        }
+      }
+      _ => {}
+    }
+    i += 1;
+  }
+}
+
+// 38;5;n and 38;2;r;g;b, returns the color and how many codes it used
+fn extended_color(codes: &[u32]) -> (Option<String>, usize) {
+  match codes {
+    [5, n, ..] => (Some(palette_color(*n)), 2),
+    [2, r, g, b, ..] => {
+      let color = format!(
+        "#{:02x}{:02x}{:02x}",
+        (*r).min(255),
+        (*g).min(255),
+        (*b).min(255)
+      );
+      (Some(color), 4)
+    }
+    _ => (None, codes.len()),
+  }
+}
+
+// Colors of the 256 color palette
+fn palette_color(n: u32) -> String {
+  match n {
+    0..=7 => COLOR_NAMES[n as usize].to_string(),
+    8..=15 => format!("bright_{}", COLOR_NAMES[(n - 8) as usize]),
+    16..=231 => {
+      let n = n - 16;
+      let level = |v: u32| if v == 0 { 0 } else { 55 + v * 40 };
+      format!(
+        "#{:02x}{:02x}{:02x}",
+        level(n / 36),
+        level((n / 6) % 6),
+        level(n % 6)
+      )
+    }
+    _ => {
+      let gray = 8 + (n.min(255) - 232) * 10;
+      format!("#{:02x}{:02x}{:02x}", gray, gray, gray)
+    }
+  }
+}
@@ -0,0 +1,193 @@
+use crate::models::ModInfo;
+use log::{ debug, info, warn };
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+// Where the app is running, so testers don't have to ask
+#[derive(Debug, Serialize)]
+struct SystemInfo {
+  app_version: String,
+  os: String,
+  os_family: String,
+  arch: String,
+  created_at: i64,
+}
+
+/// Write a zip with everything needed to look into a session of a mod
+pub async fn create_bug_report(
+  mod_info: &ModInfo,
+  session_id: &str,
+  output_path: &Path
+) -> Result<(), String> {
+  info!(
+    "Creating bug report for session {} of mod {} at {}",
+    session_id,
+    mod_info.name,
+    output_path.display()
+  );
+
+  let engine_mods = find_engine_mods(mod_info).await;
+
+  // Reading the logs and writing the zip blocks, keep it off the async runtime
+  let mod_info = mod_info.clone();
+  let session_id = session_id.to_string();
+  let output_path = output_path.to_path_buf();
+  tauri::async_runtime
+    ::spawn_blocking(move || {
+      write_bug_report(&mod_info, &session_id, &output_path, engine_mods)
+    }).await
+    .map_err(|e| format!("Failed to create bug report: {}", e))?
+}
+
+fn write_bug_report(
+  mod_info: &ModInfo,
+  session_id: &str,
+  output_path: &Path,
+  engine_mods: Option<crate::models::EngineModsResponse>
+) -> Result<(), String> {
+  let session_info = crate::terminaloutput
+    ::find_session_info(&mod_info.id, session_id)
+    .ok_or_else(|| format!("Session {} not found", session_id))?;
+  let logs = crate::terminaloutput::get_session_logs(&mod_info.id, session_id).lines;
+
+  if let Some(parent) = output_path.parent() {
+    std::fs
+      ::create_dir_all(parent)
+      .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+  }
+  let file = File::create(output_path).map_err(|e|
+    format!("Failed to create {}: {}", output_path.display(), e)
+  )?;
+  let mut zip = ZipWriter::new(file);
+
+  add_file(&mut zip, "session/output.log", logs.join("\n").as_bytes())?;
+  add_json(&mut zip, "session/session_info.json", &session_info)?;
+  add_json(&mut zip, "mod_info.json", &strip_images(mod_info))?;
+  add_json(&mut zip, "system_info.json", &system_info())?;
+
+  let metadata_path = Path::new(&mod_info.path)
+    .join(".flight")
+    .join("metadata.json");
+  if let Ok(metadata) = std::fs::read(&metadata_path) {
+    add_file(&mut zip, "metadata.json", &metadata)?;
+  }
+
+  if let Some(engine_mods) = engine_mods {
+    add_json(&mut zip, "engine_mods.json", &engine_mods)?;
+  }
+
+  // Crash files collected into the session folder
+  if
+    let Some(session_dir) = crate::terminaloutput::get_session_dir(
+      &mod_info.id,
+      session_id
+    )
+  {
+    if let Ok(entries) = std::fs::read_dir(session_dir.join("crash")) {
+      for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.is_file() {
+          continue;
+        }
+        match std::fs::read(&path) {
+          Ok(content) => {
+            let name = format!("crash/{}", entry.file_name().to_string_lossy());
+            add_file(&mut zip, &name, &content)?;
+          }
+          Err(e) => warn!("Failed to read crash file {}: {}", path.display(), e),
+        }
+      }
+    }
+  }
+
+  zip.finish().map_err(|e| format!("Failed to write bug report: {}", e))?;
+  info!("Created bug report at {}", output_path.display());
+  Ok(())
+}
+
+fn add_file(
+  zip: &mut ZipWriter<File>,
+  name: &str,
+  content: &[u8]
+) -> Result<(), String> {
+  debug!("Adding {} to bug report", name);
+  zip
+    .start_file(name, SimpleFileOptions::default())
+    .map_err(|e| format!("Failed to add {} to bug report: {}", name, e))?;
+  zip
+    .write_all(content)
+    .map_err(|e| format!("Failed to add {} to bug report: {}", name, e))
+}
+
+fn add_json<T: Serialize>(
+  zip: &mut ZipWriter<File>,
+  name: &str,
+  value: &T
+) -> Result<(), String> {
+  let content = serde_json
+    ::to_vec_pretty(value)
+    .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+  add_file(zip, name, &content)
+}
+
+// Images are big and useless for debugging
+fn strip_images(mod_info: &ModInfo) -> ModInfo {
+  let mut mod_info = mod_info.clone();
+  mod_info.icon_data = None;
+  mod_info.banner_data = None;
+  mod_info.logo_data = None;
+  if let Some(engine) = mod_info.engine.as_mut() {
+    engine.engine_icon = None;
+  }
+  mod_info
+}
+
+fn system_info() -> SystemInfo {
+  let app_version = crate::app_handle
+    ::get_global_app_handle()
+    .map(|app| app.package_info().version.to_string())
+    .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+
+  SystemInfo {
+    app_version,
+    os: std::env::consts::OS.to_string(),
+    os_family: std::env::consts::FAMILY.to_string(),
+    arch: std::env::consts::ARCH.to_string(),
+    created_at: chrono::Utc::now().timestamp(),
+  }
+}
+
+// The mods installed into the engine, if it's one we can scan
+async fn find_engine_mods(
+  mod_info: &ModInfo
+) -> Option<crate::models::EngineModsResponse> {
+  let engine = mod_info.engine.as_ref()?;
+  let engine_type = engine.engine_type.clone()?;
+  let executable_path = mod_info.executable_path.clone()?;
+  if engine_type == "other" || engine_type == "unknown" {
+    return None;
+  }
+
+  match
+    crate::commands::find_engine_mod_files(
+      executable_path,
+      engine_type,
+      engine.mods_folder_path.clone().unwrap_or_default()
+    ).await
+  {
+    Ok(mut response) => {
+      for mod_file in &mut response.mods {
+        mod_file.icon_data = None;
+      }
+      Some(response)
+    }
+    Err(e) => {
+      warn!("Failed to list engine mods for bug report: {}", e);
+      None
+    }
+  }
+}
@@ -22,15 +22,35 @@ use crate::models::{
   GBProfilePage,
   GBSubfeed,
   GBTopSubs,
+  LaunchConflict,
+  LaunchConflictReason,
+  LaunchError,
+  LaunchHooks,
+  LaunchProfile,
+  LibraryPlaytimeStats,
+  LogRecord,
+  LogRetentionPolicy,
+  LogRetentionReport,
+  LogSearchQuery,
+  LogSearchResults,
+  LogStream,
   ModDisableResult,
+  ModLaunchResult,
+  ModPlaytimeStats,
   ModInfo,
+  ModStopResult,
   ModsState,
+  RunnerConfig,
+  RunnersConfig,
+  RunningModRecord,
+  SessionLogsSince,
+  StopMethod,
 };
 use log::{ debug, error, info, warn };
 use serde_json;
 use tauri::window::{ Effect, EffectsBuilder };
 use tauri_plugin_sql::{ Migration, MigrationKind };
-use std::collections::HashMap;
+use std::collections::{ HashMap, HashSet };
 use std::path::Path;
 use std::process::Command;
 use std::sync::Mutex;
@@ -39,6 +59,11 @@ use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_deep_link::DeepLinkExt;
 use tauri_plugin_updater::UpdaterExt;
 
+// How long a mod gets to exit on its own before being killed
+const DEFAULT_STOP_GRACE_PERIOD_MS: u64 = 5000;
+// How many recent sessions the playtime stats include by default
+const DEFAULT_RECENT_SESSIONS: usize = 10;
+
 // Command to open a folder dialog and get the selected folder path
 #[tauri::command]
 pub async fn select_mod_folder(
@@ -185,12 +210,13 @@ pub async fn select_executable(
   app: tauri::AppHandle
 ) -> Result<String, String> {
   debug!("Opening executable selection dialog");
-  let file = app
-    .dialog()
-    .file()
-    .set_title("Select Executable")
-    .add_filter("Executable", &["exe"])
-    .blocking_pick_file();
+  let dialog = app.dialog().file().set_title("Select Executable");
+
+  // Native Linux builds usually have no extension, so only filter on Windows
+  #[cfg(target_os = "windows")]
+  let dialog = dialog.add_filter("Executable", &["exe"]);
+
+  let file = dialog.blocking_pick_file();
 
   match file {
     Some(path) => {
@@ -227,6 +253,9 @@ pub fn add_mod(
     }
   }
 
+  // Whatever was scanned for this folder before may be outdated
+  crate::filesystem::forget_executable_candidates(Path::new(&path));
+
   // Create ModInfo from path
   let mod_info = create_mod_info(&path)?;
   let id = mod_info.id.clone();
@@ -253,55 +282,117 @@ pub fn get_mods(mods_state: State<'_, ModsState>) -> Vec<ModInfo> {
   mods.values().cloned().collect()
 }
 
-// Command to launch a mod
-#[tauri::command]
+// Command to launch a mod, off the main thread since hooks can take a while
+#[tauri::command(async)]
 pub fn launch_mod(
   id: String,
+  queue_on_conflict: Option<bool>,
   mods_state: State<'_, ModsState>
-) -> Result<(), String> {
+) -> Result<ModLaunchResult, LaunchError> {
   info!("Attempting to launch mod with ID: {}", id);
   let mut mods = mods_state.0.lock().unwrap();
   let mod_name: String;
 
   // Get required info from the mod
-  let executable_path = match mods.get(&id) {
+  let (executable_path, runner, launch_profile, hooks, mod_path) = match
+    mods.get(&id)
+  {
     Some(mod_info) => {
       // Check if the mod is already running
       if let Some(pid) = mod_info.process_id {
         warn!("Mod is already running with PID: {}", pid);
-        return Err(format!("Mod is already running with PID: {}", pid));
+        return Err(format!("Mod is already running with PID: {}", pid).into());
+      }
+      if mods_state.1.lock().unwrap().contains(&id) {
+        warn!("Mod {} is already being launched", id);
+        return Err("Mod is already being launched".to_string().into());
       }
 
       mod_name = mod_info.name.clone();
 
-      if let Some(exe_path) = &mod_info.executable_path {
-        exe_path.clone()
+      let exe_path = if
+        let Some(exe_path) = crate::filesystem::resolve_launch_executable(
+          mod_info
+        )
+      {
+        exe_path
       } else {
         let err_msg = format!("No executable found for mod: {}", mod_info.name);
         warn!("{}", err_msg);
         crate::terminaloutput::add_log(&id, &format!("[ERROR] {}", err_msg));
-        return Err(err_msg);
-      }
+        return Err(err_msg.into());
+      };
+
+      // Windows builds need Wine or Proton everywhere else
+      let runner = if crate::runners::needs_runner(&exe_path) {
+        match crate::runners::get_runner_for_mod(mod_info) {
+          Ok(runner) => Some(runner),
+          Err(e) => {
+            warn!("{}", e);
+            crate::terminaloutput::add_log(&id, &format!("[ERROR] {}", e));
+            return Err(e.into());
+          }
+        }
+      } else {
+        None
+      };
+
+      (
+        exe_path,
+        runner,
+        mod_info.launch_profile.clone(),
+        mod_info.hooks.clone(),
+        mod_info.path.clone(),
+      )
     }
     None => {
       let err_msg = format!("Mod not found with ID: {}", id);
       warn!("{}", err_msg);
-      return Err("Mod not found".to_string());
+      return Err("Mod not found".to_string().into());
     }
   };
 
+  // Mods sharing an engine install would overwrite each other's files and saves
+  if let Some(conflict) = crate::launchconflicts::find_conflict(&id, &mods) {
+    if queue_on_conflict.unwrap_or(false) {
+      crate::launchconflicts::queue_launch(&id, &conflict.conflicting_mod_id);
+      return Ok(ModLaunchResult {
+        launched: false,
+        queued_behind: Some(conflict.conflicting_mod_id),
+      });
+    }
+    return Err(conflict_error(&mod_name, conflict));
+  }
+
   debug!("Launching executable: {}", executable_path);
 
-  // Get the executable's directory to use as the working directory
-  let exe_path_obj = Path::new(&executable_path);
-  let working_dir = exe_path_obj
-    .parent()
-    .map(|p| p.to_path_buf())
-    .ok_or_else(|| {
-      let err = "Could not determine executable's directory".to_string();
-      error!("{}", err);
-      err
-    })?;
+  // Use the profile's working directory if set, otherwise the executable's directory
+  let working_dir = match &launch_profile.working_dir {
+    Some(dir) => {
+      let dir = Path::new(&mod_path).join(dir);
+      if !dir.is_dir() {
+        let err_msg = format!(
+          "Working directory does not exist: {}",
+          dir.display()
+        );
+        warn!("{}", err_msg);
+        crate::terminaloutput::add_log(&id, &format!("[ERROR] {}", err_msg));
+        return Err(err_msg.into());
+      }
+      dir
+    }
+    None => {
+      let exe_path_obj = Path::new(&executable_path);
+      exe_path_obj
+        .parent()
+        .map(|p| p.to_path_buf())
+        .ok_or_else(|| {
+          let err = "Could not determine executable's directory".to_string();
+          error!("{}", err);
+          err
+        })?
+    }
+  };
 
   debug!("Using working directory: {}", working_dir.display());
 
@@ -320,9 +411,98 @@ pub fn launch_mod(
   );
   crate::terminaloutput::add_log(&id, &format!("Session ID: {}", session_id));
 
+  let hook_context = crate::hooks::HookContext {
+    mod_id: id.clone(),
+    mod_name: mod_name.clone(),
+    mod_path: mod_path.clone(),
+    session_id: session_id.clone(),
+    exit_code: None,
+  };
+
+  // Don't hold the state lock while the pre-launch hooks run, but keep the
+  // mod from being launched again in the meantime
+  if !hooks.pre_launch.is_empty() {
+    let launching = crate::modutils::BusyModGuard::launching(&mods_state, &id);
+    drop(mods);
+
+    let hooks_result = crate::hooks::run_hooks(
+      crate::hooks::HookStage::PreLaunch,
+      &hooks.pre_launch,
+      &hook_context
+    );
+
+    mods = mods_state.0.lock().unwrap();
+    drop(launching);
+
+    if let Err(e) = hooks_result {
+      let err_msg = format!("Not launching {}: {}", mod_name, e);
+      warn!("{}", err_msg);
+      crate::terminaloutput::add_log(&id, &format!("[ERROR] {}", err_msg));
+      crate::terminaloutput::abort_session(&id, &session_id);
+      return Err(err_msg.into());
+    }
+
+    // The mod could have been launched again while the hooks were running
+    if let Some(pid) = mods.get(&id).and_then(|m| m.process_id) {
+      let err_msg = format!("Mod is already running with PID: {}", pid);
+      warn!("{}", err_msg);
+      crate::terminaloutput::abort_session(&id, &session_id);
+      return Err(err_msg.into());
+    }
+    if let Some(conflict) = crate::launchconflicts::find_conflict(&id, &mods) {
+      let error = conflict_error(&mod_name, conflict);
+      crate::terminaloutput::add_log(&id, &format!("[ERROR] {}", error));
+      crate::terminaloutput::abort_session(&id, &session_id);
+      return Err(error);
+    }
+  }
+
+  // Wrap the executable with the runner if it needs one
+  let mut command = match &runner {
+    Some(runner) => {
+      crate::terminaloutput::add_log(
+        &id,
+        &format!("Using runner: {} ({})", runner.name, runner.path)
+      );
+      match crate::runners::build_runner_command(runner, &executable_path) {
+        Ok(command) => command,
+        Err(e) => {
+          error!("{}", e);
+          crate::terminaloutput::add_log(&id, &format!("[ERROR] {}", e));
+          crate::terminaloutput::abort_session(&id, &session_id);
+          return Err(e.into());
+        }
+      }
+    }
+    None => Command::new(&executable_path),
+  };
+
+  // Apply the mod's launch profile
+  command.args(&launch_profile.args).envs(&launch_profile.env);
+  if !launch_profile.env.is_empty() {
+    let mut env_names = launch_profile.env.keys().cloned().collect::<Vec<_>>();
+    env_names.sort();
+    crate::terminaloutput::add_log(
+      &id,
+      &format!("Environment overrides: {}", env_names.join(", "))
+    );
+  }
+
+  let command_line = crate::processes::format_command_line(&command);
+  crate::terminaloutput::add_log(&id, &format!("Command: {}", command_line));
+  crate::terminaloutput::update_session_info(&id, &session_id, |session_info| {
+    session_info.command_line = Some(command_line.clone());
+  });
+
+  // Crash files written after this point belong to this session
+  let launched_at = std::time::SystemTime::now();
+
+  // Keep the mod and anything it spawns together so they can be stopped at once
+  crate::processes::isolate_process_group(&mut command);
+
   // Launch the executable with output capture
   match
-    Command::new(&executable_path)
+    command
       .current_dir(&working_dir) // Set the working directory to the mod's directory
       .stdout(std::process::Stdio::piped()) // Capture stdout
       .stderr(std::process::Stdio::piped()) // Capture stderr
@@ -331,6 +511,25 @@ pub fn launch_mod(
     Ok(mut child) => {
       let pid = child.id();
       info!("Successfully launched: {} with PID: {}", executable_path, pid);
+      crate::processes::track_process_tree(&child);
+
+      // Persist the running mod so it can be found again if Fridaylight restarts
+      if let Some(session) = crate::terminaloutput::get_session_info(&session_id) {
+        crate::resourcemonitor::start_monitoring(
+          &id,
+          &session_id,
+          pid,
+          session.start_time
+        );
+        crate::runningmods::remember_running_mod(RunningModRecord {
+          mod_id: id.clone(),
+          pid,
+          executable_path: executable_path.clone(),
+          process_path: crate::processes::get_process_executable(pid),
+          session,
+        });
+      }
+
       // Store the process ID and session info in the ModInfo and update last_played timestamp
       if let Some(mod_info) = mods.get_mut(&id) {
         mod_info.process_id = Some(pid);
@@ -383,9 +582,18 @@ pub fn launch_mod(
         }
       }
 
+      // Queued launches start in the background, this is how the UI hears of them
+      if let Err(e) = crate::app_handle::emit_event("mod-launched", id.clone()) {
+        error!("Failed to emit mod-launched event: {}", e);
+      }
+
       // Clone the id for use in threads
       let id_clone = id.clone();
       let id_clone2 = id.clone();
+      let session_id_clone = session_id.clone();
+      let session_id_clone2 = session_id.clone();
+      let stderr_tail = crate::crashreports::new_stderr_tail();
+      let stderr_tail_for_reader = stderr_tail.clone();
 
       // Set up stdout and stderr capturing in background threads
       if let Some(stdout) = child.stdout.take() {
@@ -394,31 +602,59 @@ pub fn launch_mod(
           let reader = BufReader::new(stdout);
           for line in reader.lines() {
             if let Ok(line) = line {
-              crate::terminaloutput::add_log(&id_clone, &line);
+              crate::terminaloutput::add_output_log(
+                &id_clone,
+                &session_id_clone,
+                LogStream::Stdout,
+                &line
+              );
             }
           }
         });
       }
 
-      if let Some(stderr) = child.stderr.take() {
+      let stderr_reader = child.stderr.take().map(|stderr| {
         std::thread::spawn(move || {
           use std::io::{ BufRead, BufReader };
           let reader = BufReader::new(stderr);
           for line in reader.lines() {
             if let Ok(line) = line {
-              crate::terminaloutput::add_log(
+              crate::crashreports::push_stderr_line(
+                &stderr_tail_for_reader,
+                &crate::ansi::strip_ansi(&line)
+              );
+              crate::terminaloutput::add_output_log(
                 &id_clone2,
-                &format!("[ERROR] {}", line)
+                &session_id_clone2,
+                LogStream::Stderr,
+                &line
               );
             }
           }
-        });
-      }
+        })
+      });
+
+      let crash_context = crate::crashreports::CrashContext {
+        mod_id: id.clone(),
+        mod_name: mod_name.clone(),
+        mod_path: mod_path.clone(),
+        executable_dir: Path::new(&executable_path)
+          .parent()
+          .map(|p| p.to_path_buf())
+          .unwrap_or_else(|| working_dir.clone()),
+        session_id: session_id.clone(),
+        started_at: launched_at,
+      };
 
       // Monitor the process in a background thread to update state when it exits
       let id_for_thread = id.to_string();
       let session_id_for_thread = session_id.clone();
+      let post_exit_hooks = hooks.post_exit;
+      // Until the thread below is done, the mod can't be launched again and
+      // nothing waiting for it starts, even if it gets stopped
+      let running = crate::modutils::BusyModGuard::running(&mods_state, &id);
       std::thread::spawn(move || {
+        let _running = running;
         match child.wait() {
           Ok(status) => {
             let exit_code = status.code();
@@ -427,21 +663,60 @@ pub fn launch_mod(
             info!("{}", exit_message);
             crate::terminaloutput::add_log(&id_for_thread, &exit_message);
 
+            // Report helpers that outlived the game (crash handlers and such)
+            for orphan in crate::processes::find_process_tree(pid) {
+              warn!("Orphaned process left running: {}", orphan);
+              crate::terminaloutput::add_session_log(
+                &id_for_thread,
+                &session_id_for_thread,
+                &format!("[WARNING] Orphaned process still running: {}", orphan)
+              );
+            }
+            crate::processes::release_process_tree(pid);
+
+            // Give the stderr reader a moment to catch up before checking for a crash,
+            // it can stay blocked for longer if an orphan keeps the pipe open
+            if let Some(reader) = stderr_reader {
+              let deadline =
+                std::time::Instant::now() + std::time::Duration::from_secs(1);
+              while !reader.is_finished() && std::time::Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+              }
+            }
+            crate::crashreports::handle_session_exit(
+              &crash_context,
+              exit_code,
+              &stderr_tail
+            );
+
+            // Post-exit hooks log into the session, so run them before it ends
+            if !post_exit_hooks.is_empty() {
+              let context = crate::hooks::HookContext {
+                exit_code,
+                ..hook_context
+              };
+              if
+                let Err(e) = crate::hooks::run_hooks(
+                  crate::hooks::HookStage::PostExit,
+                  &post_exit_hooks,
+                  &context
+                )
+              {
+                warn!("Post-exit hooks failed for mod {}: {}", id_for_thread, e);
+              }
+            }
+
             // End the session with the exit code
             crate::terminaloutput::end_session(
               &id_for_thread,
               &session_id_for_thread,
               exit_code
             );
-
-            // The process has exited, so we need to update the mod's running state
-            // We do this by directly invoking the set_mod_not_running function
-            // which will handle the state update in a thread-safe way
-            crate::modutils::set_mod_not_running(&id_for_thread);
           }
           Err(e) => {
             let error_msg = format!("Failed to wait for process: {}", e);
             error!("{}", error_msg);
+            crate::processes::release_process_tree(pid);
             crate::terminaloutput::add_log(
               &id_for_thread,
               &format!("[ERROR] {}", error_msg)
@@ -455,19 +730,47 @@ pub fn launch_mod(
             );
           }
         }
+        // Dropping the guard marks the mod as not running and starts queued launches
       });
 
-      Ok(())
+      Ok(ModLaunchResult {
+        launched: true,
+        queued_behind: None,
+      })
     }
     Err(e) => {
       let error_msg = format!("Failed to launch executable: {}", e);
       error!("{}", error_msg);
       crate::terminaloutput::add_log(&id, &format!("[ERROR] {}", error_msg));
-      Err(error_msg)
+      crate::terminaloutput::abort_session(&id, &session_id);
+      Err(error_msg.into())
     }
   }
 }
 
+// Build the error for a launch blocked by another running mod
+fn conflict_error(mod_name: &str, conflict: LaunchConflict) -> LaunchError {
+  let shared = match conflict.reason {
+    LaunchConflictReason::SharedExecutableDirectory => "executable folder",
+    LaunchConflictReason::SharedModsFolder => "mods folder",
+  };
+  let message = format!(
+    "Can't launch {} while {} is running, they share the same {} ({})",
+    mod_name,
+    conflict.conflicting_mod_name,
+    shared,
+    conflict.shared_path
+  );
+  warn!("{}", message);
+  LaunchError::Conflict { message, conflict }
+}
+
+// Command to cancel a launch waiting for a conflicting mod to exit
+#[tauri::command]
+pub fn cancel_queued_launch(id: String) -> bool {
+  crate::launchconflicts::cancel_queued_launch(&id)
+}
+
 // Command to check if a mod is running
 #[tauri::command]
 pub fn is_mod_running(id: String, mods_state: State<'_, ModsState>) -> bool {
@@ -530,71 +833,116 @@ pub fn is_mod_running(id: String, mods_state: State<'_, ModsState>) -> bool {
 
 // Command to stop a running mod
 #[tauri::command]
-pub fn stop_mod(
+pub async fn stop_mod(
   id: String,
+  grace_period_ms: Option<u64>,
   mods_state: State<'_, ModsState>
-) -> Result<(), String> {
+) -> Result<ModStopResult, String> {
   info!("Attempting to stop mod with ID: {}", id);
-  let mut mods = mods_state.0.lock().unwrap();
-
-  if let Some(mod_info) = mods.get_mut(&id) {
-    if let Some(pid) = mod_info.process_id {
-      info!("Stopping mod: {} with PID: {}", mod_info.name, pid);
-
-      #[cfg(target_os = "windows")]
-      {
-        use std::process::Command;
-        // On Windows, use taskkill to terminate the process
-        match
-          Command::new("taskkill")
-            .args(&["/PID", &pid.to_string(), "/F"])
-            .output()
-        {
-          Ok(_) => {
-            info!("Successfully stopped process with PID: {}", pid);
-            mod_info.process_id = None;
-
-            // Also update the global state
-            match crate::models::GLOBAL_MODS_STATE.lock() {
-              Ok(mut global_mods) => {
-                if let Some(global_mod_info) = global_mods.get_mut(&id) {
-                  global_mod_info.process_id = None;
-                  info!(
-                    "Updated global state for mod {}: pid=None",
-                    mod_info.name
-                  );
-                } else {
-                  warn!(
-                    "Mod {} not found in global state when stopping",
-                    mod_info.name
-                  );
-                }
-              }
-              _ => {
-                warn!(
-                  "Failed to acquire lock on global state when stopping mod"
-                );
-              }
-            }
 
-            Ok(())
-          }
-          Err(e) => {
-            let error_msg = format!("Failed to stop process: {}", e);
-            error!("{}", error_msg);
-            Err(error_msg)
+  // Don't hold the lock while waiting, the process monitor needs it once the mod exits
+  let (pid, mod_name, session_id) = {
+    let mods = mods_state.0.lock().unwrap();
+    match mods.get(&id) {
+      Some(mod_info) =>
+        match mod_info.process_id {
+          Some(pid) =>
+            (pid, mod_info.name.clone(), mod_info.current_session_id.clone()),
+          None => {
+            let msg = format!("Mod {} is not running", mod_info.name);
+            warn!("{}", msg);
+            return Err(msg);
           }
         }
+      None => {
+        let err_msg = format!("Mod not found with ID: {}", id);
+        warn!("{}", err_msg);
+        return Err(err_msg);
       }
-    } else {
-      let msg = format!("Mod {} is not running", mod_info.name);
-      warn!("{}", msg);
-      Err(msg)
     }
-  } else {
-    let err_msg = format!("Mod not found with ID: {}", id);
-    warn!("{}", err_msg);
-    Err(err_msg)
+  };
+
+  let grace_period = std::time::Duration::from_millis(
+    grace_period_ms.unwrap_or(DEFAULT_STOP_GRACE_PERIOD_MS)
+  );
+  info!("Stopping mod: {} with PID: {}", mod_name, pid);
+  if let Some(session_id) = &session_id {
+    crate::crashreports::mark_stop_requested(session_id);
+  }
+  crate::terminaloutput::add_log(
+    &id,
+    &format!("Stopping process with PID: {}", pid)
+  );
+
+  let result = tauri::async_runtime
+    ::spawn_blocking(move || {
+      crate::processes::terminate_process_tree(pid, grace_period)
+    }).await
+    .map_err(|e| format!("Failed to stop process: {}", e))?;
+
+  match result {
+    Ok(stop) => {
+      let method = stop.method;
+      if !stop.stopped_children.is_empty() {
+        crate::terminaloutput::add_log(
+          &id,
+          &format!(
+            "Stopped {} child processes: {}",
+            stop.stopped_children.len(),
+            stop.stopped_children
+              .iter()
+              .map(|p| p.to_string())
+              .collect::<Vec<_>>()
+              .join(", ")
+          )
+        );
+      }
+      for survivor in &stop.survivors {
+        warn!("Orphaned process left running by {}: {}", mod_name, survivor);
+        crate::terminaloutput::add_log(
+          &id,
+          &format!("[WARNING] Orphaned process still running: {}", survivor)
+        );
+      }
+
+      let message = match method {
+        StopMethod::Graceful => format!("{} exited gracefully", mod_name),
+        StopMethod::Forced =>
+          format!(
+            "{} did not exit within {} ms and was killed",
+            mod_name,
+            grace_period.as_millis()
+          ),
+        StopMethod::AlreadyExited => format!("{} had already exited", mod_name),
+        StopMethod::ChildrenStopped =>
+          format!(
+            "{} had already exited, stopped {} processes it left running",
+            mod_name,
+            stop.stopped_children.len()
+          ),
+      };
+      info!("{}", message);
+      crate::terminaloutput::add_log(&id, &message);
+
+      crate::modutils::set_mod_not_running(&id);
+
+      Ok(ModStopResult {
+        success: true,
+        method: Some(method),
+        message,
+      })
+    }
+    Err(e) => {
+      let error_msg = format!("Failed to stop process: {}", e);
+      error!("{}", error_msg);
+      crate::terminaloutput::add_log(&id, &format!("[ERROR] {}", error_msg));
+
+      Ok(ModStopResult {
+        success: false,
+        method: None,
+        message: error_msg,
+      })
+    }
   }
 }
 
@@ -655,14 +1003,108 @@ pub async fn download_gamebanana_mod_command(
   update_existing: Option<bool>,
   app: tauri::AppHandle
 ) -> Result<ModInfo, String> {
-  download_gamebanana_mod(
-    file,
-    info,
-    install_location,
-    folder_name,
-    update_existing,
-    app
-  ).await
+  // The frontend knows GameBanana downloads by their file ID
+  crate::downloadqueue::run(&app, file.id_row, info.name.clone(), |cancel_token| {
+    download_gamebanana_mod(
+      file.clone(),
+      info.clone(),
+      install_location.clone(),
+      folder_name.clone(),
+      update_existing,
+      cancel_token,
+      app.clone()
+    )
+  }).await
+}
+
+// Command to cancel a download or extraction by the ID it was started with,
+// whether it's running or still waiting in the queue
+#[tauri::command]
+pub fn cancel_download(download_id: i64) -> Result<(), String> {
+  if crate::downloadqueue::cancel(download_id) {
+    Ok(())
+  } else {
+    Err(format!("No running download with ID {}", download_id))
+  }
+}
+
+// Command to list the downloads in the queue, including recently finished ones
+#[tauri::command]
+pub fn list_downloads() -> Vec<crate::models::DownloadJob> {
+  crate::downloadqueue::list_jobs()
+}
+
+// Command to pause a queued or running download
+#[tauri::command]
+pub fn pause_download(download_id: i64) -> Result<(), String> {
+  crate::downloadqueue::pause(download_id)
+}
+
+// Command to put a paused download back in the queue
+#[tauri::command]
+pub fn resume_download(download_id: i64) -> Result<(), String> {
+  crate::downloadqueue::resume(download_id)
+}
+
+// Command to move a waiting download to another place in the queue, 0 is next
+#[tauri::command]
+pub fn move_download(download_id: i64, position: usize) -> Result<(), String> {
+  crate::downloadqueue::move_job(download_id, position)
+}
+
+// Command to get the backend download settings
+#[tauri::command]
+pub fn get_download_settings() -> crate::models::DownloadSettings {
+  crate::downloadsettings::get_settings()
+}
+
+// Command to change the backend download settings
+#[tauri::command]
+pub fn set_download_settings(
+  settings: crate::models::DownloadSettings,
+  app: tauri::AppHandle
+) -> Result<(), String> {
+  crate::downloadsettings::set_settings(&settings)?;
+  // The concurrency limit might have gone up
+  crate::downloadqueue::queue_changed();
+  // And the cache size limit might have gone down
+  crate::downloadcache::enforce_size_cap(&app, 0);
+  Ok(())
+}
+
+// Command to list the archives kept after installing them
+#[tauri::command]
+pub fn list_archived_downloads() -> Result<
+  Vec<crate::models::ArchivedDownload>,
+  String
+> {
+  crate::downloadcache::list_archives()
+}
+
+// Command to delete an archive from the archive library
+#[tauri::command]
+pub fn delete_archived_download(file_name: String) -> Result<(), String> {
+  crate::downloadcache::delete_archive(&file_name)
+}
+
+// Command to install an archive from the archive library again
+#[tauri::command]
+pub async fn reinstall_archived_download(
+  file_name: String,
+  download_id: i64,
+  app: tauri::AppHandle
+) -> Result<ModInfo, String> {
+  let name = crate::downloadcache
+    ::get_archive(&file_name)?
+    .name.unwrap_or_else(|| file_name.clone());
+  crate::downloadqueue::run(&app, download_id, name, |cancel_token| {
+    crate::download::reinstall_archived_download(
+      file_name.clone(),
+      download_id,
+      cancel_token,
+      app.clone()
+    )
+  }).await
 }
 
 #[tauri::command]
@@ -675,30 +1117,65 @@ pub async fn download_engine_command(
   app: tauri::AppHandle
 ) -> Result<ModInfo, String> {
   info!("Starting direct download process for {} engine", engine_type);
-  crate::download::download_engine(
-    engine_type,
-    install_location,
-    custom_name,
-    update_existing,
-    download_id,
-    app
-  ).await
+  crate::downloadqueue::run(&app, download_id, engine_type.clone(), |cancel_token| {
+    crate::download::download_engine(
+      engine_type.clone(),
+      install_location.clone(),
+      custom_name.clone(),
+      update_existing,
+      download_id,
+      cancel_token,
+      app.clone()
+    )
+  }).await
 }
 
 // Command to sync/update mods from database
 #[tauri::command]
 pub async fn sync_mods_from_database(
-  mods_data: Vec<ModInfo>,
+  mut mods_data: Vec<ModInfo>,
   mods_state: State<'_, ModsState>
 ) -> Result<(), String> {
   info!("Syncing {} mods from database to ModsState", mods_data.len());
 
+  // Mods that moved or got a different executable need their folder scanned again
+  {
+    let mods = mods_state.0.lock().unwrap();
+    for mod_info in &mods_data {
+      let changed = mods
+        .get(&mod_info.id)
+        .is_some_and(|existing_mod| {
+          existing_mod.path != mod_info.path ||
+            existing_mod.executable_path != mod_info.executable_path
+        });
+      if changed {
+        crate::filesystem::forget_executable_candidates(Path::new(&mod_info.path));
+      }
+    }
+  }
+
+  // The database doesn't store executable candidates, find them before taking
+  // the lock so launching and conflict checks don't have to scan folders
+  for mod_info in mods_data.iter_mut() {
+    if mod_info.executable_candidates.is_empty() {
+      mod_info.executable_candidates =
+        crate::filesystem::cached_executable_candidates(
+          Path::new(&mod_info.path)
+        );
+    }
+  }
+
   let mut mods = mods_state.0.lock().unwrap();
   let mut updated_count = 0;
   let mut added_count = 0;
 
   // For each mod in the database, check if it exists in the state
   for mut new_mod_info in mods_data {
+    // Launch settings are stored by the backend, not the database
+    crate::launchsettings::apply_launch_settings(&mut new_mod_info);
+    // Mods still running from before a restart
+    crate::runningmods::apply_running_state(&mut new_mod_info);
+
     match mods.get(&new_mod_info.id) {
       Some(existing_mod) => {
         // Check if the mod has actually changed (excluding process_id)
@@ -707,9 +1184,17 @@ pub async fn sync_mods_from_database(
         let mut new_mod_copy = new_mod_info.clone();
         new_mod_copy.process_id = None; // Ignore process_id for comparison
 
+        // The database doesn't store executable candidates, keep the ones we found
+        if new_mod_copy.executable_candidates.is_empty() {
+          new_mod_copy.executable_candidates =
+            existing_mod.executable_candidates.clone();
+        }
+
         if existing_mod_copy != new_mod_copy {
           // Mod has changed, preserve the process_id and update
           new_mod_info.process_id = existing_mod.process_id;
+          new_mod_info.executable_candidates =
+            new_mod_copy.executable_candidates.clone();
 
           if let Some(pid) = new_mod_info.process_id {
             info!(
@@ -1075,14 +1560,87 @@ pub fn get_mod_logs(id: String) -> Vec<String> {
 
 // Command to get terminal logs for a specific mod session
 #[tauri::command]
-pub fn get_mod_session_logs(id: String, session_id: String) -> Vec<String> {
+pub fn get_mod_session_logs(
+  id: String,
+  session_id: String
+) -> crate::models::SessionLogs {
   crate::terminaloutput::get_session_logs(&id, &session_id)
 }
 
 // Command to get current session logs for a mod
 #[tauri::command]
-pub fn get_mod_current_session_logs(id: String) -> Vec<String> {
-  crate::terminaloutput::get_logs(&id)
+pub fn get_mod_current_session_logs(id: String) -> crate::models::SessionLogs {
+  crate::terminaloutput::get_current_session_logs(&id)
+}
+
+// Command to get parsed log records for a specific mod's current session
+#[tauri::command]
+pub fn get_mod_structured_logs(id: String) -> Vec<LogRecord> {
+  crate::terminaloutput::get_records(&id)
+}
+
+// Command to get parsed log records for a specific mod session
+#[tauri::command]
+pub fn get_mod_session_structured_logs(
+  id: String,
+  session_id: String
+) -> Vec<LogRecord> {
+  crate::terminaloutput::get_session_records(&id, &session_id)
+}
+
+// Command to get the logs of a session printed after an offset,
+// used by viewers catching up on what they missed from mod-log-line events
+#[tauri::command]
+pub fn get_session_logs_since(
+  mod_id: String,
+  session_id: String,
+  offset: usize
+) -> SessionLogsSince {
+  crate::terminaloutput::get_session_records_since(&mod_id, &session_id, offset)
+}
+
+// Command to search the logs of all sessions, or all sessions of one mod
+#[tauri::command(async)]
+pub fn search_mod_logs(query: LogSearchQuery) -> Result<LogSearchResults, String> {
+  crate::logsearch::search_logs(&query)
+}
+
+// Command to get the log retention policy
+#[tauri::command]
+pub fn get_log_retention_policy() -> LogRetentionPolicy {
+  crate::logretention::get_policy()
+}
+
+// Command to change the log retention policy, prunes right away
+#[tauri::command(async)]
+pub fn set_log_retention_policy(
+  policy: LogRetentionPolicy
+) -> Result<LogRetentionReport, String> {
+  crate::logretention::set_policy(&policy)?;
+  Ok(crate::logretention::enforce_policy())
+}
+
+// Command to write a bug report zip for a mod session to the chosen path
+#[tauri::command]
+pub async fn export_session_bug_report(
+  id: String,
+  session_id: String,
+  output_path: String,
+  mods_state: State<'_, ModsState>
+) -> Result<(), String> {
+  let mod_info = {
+    let mods = mods_state.0.lock().unwrap();
+    mods
+      .get(&id)
+      .cloned()
+      .ok_or_else(|| format!("Mod with ID {} not found", id))?
+  };
+
+  crate::bugreport::create_bug_report(
+    &mod_info,
+    &session_id,
+    Path::new(&output_path)
+  ).await
 }
 
 // Command to list all sessions for a specific mod
@@ -1090,51 +1648,24 @@ pub fn get_mod_current_session_logs(id: String) -> Vec<String> {
 pub fn list_mod_sessions(
   id: String
 ) -> Result<Vec<crate::models::SessionInfo>, String> {
-  // This will search through all session directories to find sessions for this mod
-  let logs_dir = match crate::terminaloutput::LOGS_DIR.as_ref() {
-    Ok(path_buf) => path_buf,
-    Err(e) => {
-      return Err(format!("Failed to resolve logs directory: {}", e));
-    }
-  };
-
-  let mut sessions = Vec::new();
-
-  if let Ok(entries) = std::fs::read_dir(logs_dir) {
-    for entry in entries.filter_map(|e| e.ok()) {
-      if entry.path().is_dir() {
-        // Check each session directory for this mod
-        if let Ok(session_entries) = std::fs::read_dir(entry.path()) {
-          for session_entry in session_entries.filter_map(|e| e.ok()) {
-            if session_entry.path().is_dir() {
-              let session_info_path = session_entry
-                .path()
-                .join("session_info.json");
-              if session_info_path.exists() {
-                if let Ok(file) = std::fs::File::open(&session_info_path) {
-                  if
-                    let Ok(session_info) = serde_json::from_reader::<
-                      _,
-                      crate::models::SessionInfo
-                    >(file)
-                  {
-                    if session_info.mod_id == id {
-                      sessions.push(session_info);
-                    }
-                  }
-                }
-              }
-            }
-          }
-        }
-      }
-    }
-  }
+  Ok(crate::sessionindex::list_sessions(&id))
+}
 
-  // Sort sessions by start time (newest first)
-  sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+// Command to get a page of saved sessions, of one mod or of every mod
+#[tauri::command]
+pub fn query_mod_sessions(
+  id: Option<String>,
+  offset: Option<usize>,
+  limit: Option<usize>
+) -> crate::models::SessionPage {
+  crate::sessionindex::query_sessions(id.as_deref(), offset.unwrap_or(0), limit)
+}
 
-  Ok(sessions)
+// Command to rebuild the session index from the session folders on disk
+#[tauri::command(async)]
+pub fn rebuild_session_index() -> usize {
+  info!("Rebuilding session index");
+  crate::sessionindex::rebuild_index()
 }
 
 // Command to clear in-memory terminal logs for a specific mod's current session, while saving them to a file
@@ -1162,46 +1693,7 @@ pub fn clear_session_logs(
   }
 
   // Remove from disk
-  let logs_dir = match crate::terminaloutput::LOGS_DIR.as_ref() {
-    Ok(path_buf) => path_buf,
-    Err(e) => {
-      return Err(format!("Failed to resolve logs directory: {}", e));
-    }
-  };
-
-  if let Ok(entries) = std::fs::read_dir(logs_dir) {
-    for entry in entries.filter_map(|e| e.ok()) {
-      if entry.path().is_dir() {
-        let session_dir = entry.path().join(&session_id);
-        if session_dir.exists() {
-          // Check if this session belongs to our mod
-          let session_info_path = session_dir.join("session_info.json");
-          if session_info_path.exists() {
-            if let Ok(file) = std::fs::File::open(&session_info_path) {
-              if
-                let Ok(session_info) = serde_json::from_reader::<
-                  _,
-                  crate::models::SessionInfo
-                >(file)
-              {
-                if session_info.mod_id == id {
-                  // This is our session, delete it
-                  if let Err(e) = std::fs::remove_dir_all(&session_dir) {
-                    return Err(
-                      format!("Failed to delete session directory: {}", e)
-                    );
-                  }
-                  break;
-                }
-              }
-            }
-          }
-        }
-      }
-    }
-  }
-
-  Ok(())
+  crate::terminaloutput::delete_session_folder(&id, &session_id)
 }
 
 // Command to clear all logs for a specific mod, including all sessions
@@ -1312,6 +1804,7 @@ pub async fn super_delete_mod(
 
       // Remove the mod from our state
       mods.remove(&id);
+      crate::launchsettings::remove_mod_launch_settings(&id);
 
       Ok(())
     }
@@ -1381,6 +1874,152 @@ pub fn check_engine_folder_exists(
   )
 }
 
+// Command to get the configured Wine/Proton runners
+#[tauri::command]
+pub fn get_runners() -> RunnersConfig {
+  crate::runners::get_runners()
+}
+
+// Command to add or update a runner
+#[tauri::command]
+pub fn save_runner(runner: RunnerConfig) -> Result<RunnerConfig, String> {
+  crate::runners::save_runner(runner)
+}
+
+// Command to remove a runner
+#[tauri::command]
+pub fn remove_runner(id: String) -> Result<(), String> {
+  crate::runners::remove_runner(&id)
+}
+
+// Command to set the runner used when a mod doesn't pick one
+#[tauri::command]
+pub fn set_default_runner(id: Option<String>) -> Result<(), String> {
+  crate::runners::set_default_runner(id)
+}
+
+// Command to look for Wine and Proton installs
+#[tauri::command]
+pub fn detect_runners() -> Vec<RunnerConfig> {
+  crate::runners::detect_runners()
+}
+
+// Command to pick the runner a mod is launched with (None uses the default)
+#[tauri::command]
+pub fn set_mod_runner(
+  id: String,
+  runner_id: Option<String>
+) -> Result<(), String> {
+  if let Some(runner_id) = &runner_id {
+    if
+      !crate::runners
+        ::get_runners()
+        .runners.iter()
+        .any(|r| &r.id == runner_id)
+    {
+      return Err(format!("Runner not found: {}", runner_id));
+    }
+  }
+
+  crate::launchsettings::update_mod_launch_settings(&id, |settings| {
+    settings.runner_id = runner_id;
+  })?;
+  Ok(())
+}
+
+// Command to get a mod's launch arguments, environment and working directory
+#[tauri::command]
+pub fn get_mod_launch_profile(id: String) -> LaunchProfile {
+  crate::launchsettings::get_mod_launch_settings(&id).launch_profile
+}
+
+// Command to change a mod's launch arguments, environment and working directory
+#[tauri::command]
+pub fn set_mod_launch_profile(
+  id: String,
+  mut profile: LaunchProfile
+) -> Result<LaunchProfile, String> {
+  profile.args.retain(|arg| !arg.is_empty());
+  if let Some(name) = profile.env.keys().find(|k| k.is_empty() || k.contains('=')) {
+    return Err(format!("Invalid environment variable name: '{}'", name));
+  }
+  profile.working_dir = profile.working_dir
+    .map(|dir| dir.trim().to_string())
+    .filter(|dir| !dir.is_empty());
+
+  // Values can hold tokens or private paths, only the env var names get logged
+  let mut env_names = profile.env.keys().cloned().collect::<Vec<_>>();
+  env_names.sort();
+  info!(
+    "Updating launch profile for mod {}: {} args, env vars [{}], working dir {}",
+    id,
+    profile.args.len(),
+    env_names.join(", "),
+    if profile.working_dir.is_some() { "set" } else { "default" }
+  );
+  let settings = crate::launchsettings::update_mod_launch_settings(
+    &id,
+    |settings| {
+      settings.launch_profile = profile;
+    }
+  )?;
+  Ok(settings.launch_profile)
+}
+
+// Command to get the commands run before a mod launches and after it exits
+#[tauri::command]
+pub fn get_mod_hooks(id: String) -> LaunchHooks {
+  crate::launchsettings::get_mod_launch_settings(&id).hooks
+}
+
+// Command to change the commands run before a mod launches and after it exits
+#[tauri::command]
+pub fn set_mod_hooks(
+  id: String,
+  mut hooks: LaunchHooks
+) -> Result<LaunchHooks, String> {
+  hooks.pre_launch.retain(|hook| !hook.trim().is_empty());
+  hooks.post_exit.retain(|hook| !hook.trim().is_empty());
+
+  info!("Updating hooks for mod {}: {:?}", id, hooks);
+  let settings = crate::launchsettings::update_mod_launch_settings(
+    &id,
+    |settings| {
+      settings.hooks = hooks;
+    }
+  )?;
+  Ok(settings.hooks)
+}
+
+// Command to get the playtime stats of a mod
+#[tauri::command]
+pub fn get_mod_playtime_stats(
+  id: String,
+  recent_count: Option<usize>
+) -> ModPlaytimeStats {
+  crate::sessionhistory::get_mod_stats(
+    &id,
+    recent_count.unwrap_or(DEFAULT_RECENT_SESSIONS)
+  )
+}
+
+// Command to get the playtime stats of the whole library
+#[tauri::command]
+pub fn get_library_playtime_stats(
+  recent_count: Option<usize>
+) -> LibraryPlaytimeStats {
+  crate::sessionhistory::get_library_stats(
+    recent_count.unwrap_or(DEFAULT_RECENT_SESSIONS)
+  )
+}
+
+// Command to check whether mod resource usage is sampled on this platform,
+// there are no mod-resource-usage events or stored usage where it isn't
+#[tauri::command]
+pub fn is_resource_monitoring_supported() -> bool {
+  crate::resourcemonitor::is_supported()
+}
+
 async fn update(app: tauri::AppHandle) -> tauri_plugin_updater::Result<()> {
   if let Some(update) = app.updater()?.check().await? {
     let mut downloaded = 0;
@@ -1405,7 +2044,10 @@ async fn update(app: tauri::AppHandle) -> tauri_plugin_updater::Result<()> {
 // Setup function for Tauri application
 pub fn run() {
   // Create a shared mods state that will be used throughout the application
-  let mods_state = ModsState(Mutex::new(HashMap::new()));
+  let mods_state = ModsState(
+    Mutex::new(HashMap::new()),
+    Mutex::new(HashSet::new())
+  );
 
   // Store a reference to this state in our global state for background thread access
   {
@@ -1449,12 +2091,25 @@ pub fn run() {
           Err(e) => error!("Failed to register deep link protocol: {}", e),
         }
       }
-      Ok(
-        logger::init(&app.handle()).map_err(|e| {
-          error!("Logger initialization failed: {}", e);
-          e
-        })?
-      )
+      logger::init(&app.handle()).map_err(|e| {
+        error!("Logger initialization failed: {}", e);
+        e
+      })?;
+
+      // Pick up mods that were still running when Fridaylight closed
+      crate::runningmods::reconcile_on_startup();
+      // Move session logs out of the folders grouped by mod name, then prune
+      // old ones, without holding up startup
+      std::thread::spawn(|| {
+        crate::sessionindex::migrate_name_based_folders();
+        crate::logretention::enforce_policy();
+      });
+      // Clean up downloads that were never installed
+      let cache_handle = app.handle().clone();
+      std::thread::spawn(move || {
+        crate::downloadcache::clean_up_on_startup(&cache_handle);
+      });
+      Ok(())
     })
     .invoke_handler(
       tauri::generate_handler![
@@ -1468,6 +2123,16 @@ pub fn run() {
         get_featured_mods_command,
         get_mod_info_command,
         download_gamebanana_mod_command,
+        cancel_download,
+        list_downloads,
+        pause_download,
+        resume_download,
+        move_download,
+        get_download_settings,
+        set_download_settings,
+        list_archived_downloads,
+        delete_archived_download,
+        reinstall_archived_download,
         download_engine_command,
         sync_mods_from_database,
         select_mods_parent_folder,
@@ -1484,7 +2149,16 @@ pub fn run() {
         get_mod_logs,
         get_mod_session_logs,
         get_mod_current_session_logs,
+        get_mod_structured_logs,
+        get_mod_session_structured_logs,
+        get_session_logs_since,
+        search_mod_logs,
+        get_log_retention_policy,
+        set_log_retention_policy,
+        export_session_bug_report,
         list_mod_sessions,
+        query_mod_sessions,
+        rebuild_session_index,
         clear_mod_logs,
         clear_session_logs,
         clear_all_mod_logs,
@@ -1498,7 +2172,21 @@ pub fn run() {
         compare_update_semver,
         save_mod_metadata,
         get_url_as_base64,
-        remove_all_mods_command
+        remove_all_mods_command,
+        get_runners,
+        save_runner,
+        remove_runner,
+        set_default_runner,
+        detect_runners,
+        set_mod_runner,
+        get_mod_launch_profile,
+        set_mod_launch_profile,
+        get_mod_hooks,
+        set_mod_hooks,
+        get_mod_playtime_stats,
+        get_library_playtime_stats,
+        is_resource_monitoring_supported,
+        cancel_queued_launch
       ]
     )
     .run(tauri::generate_context!())
@@ -0,0 +1,280 @@
+use crate::models::ModCrashed;
+use lazy_static::lazy_static;
+use log::{ debug, error, info, warn };
+use std::collections::{ HashSet, VecDeque };
+use std::path::{ Path, PathBuf };
+use std::sync::{ Arc, Mutex };
+use std::time::SystemTime;
+
+// How many stderr lines to keep around for crash reports
+const LAST_STDERR_LINES: usize = 20;
+// Folders engines write crash dumps into, relative to the exe or mod folder
+const CRASH_DIRS: [&str; 4] = ["crash", "crashes", "crash-reports", "crashdumps"];
+// Folders with logs that are worth keeping next to a crash dump
+const LOG_DIRS: [&str; 1] = ["logs"];
+// Don't copy anything huge (like full memory dumps) into the session folder
+const MAX_COLLECTED_FILE_SIZE: u64 = 50 * 1024 * 1024;
+
+// The last stderr lines of a running mod
+pub type StderrTail = Arc<Mutex<VecDeque<String>>>;
+
+// Sessions the user stopped on purpose, those didn't crash
+lazy_static! {
+  static ref STOP_REQUESTED: Mutex<HashSet<String>> = Mutex::new(
+    HashSet::new()
+  );
+}
+
+// Everything needed to look for crash reports once a mod exits
+#[derive(Debug, Clone)]
+pub struct CrashContext {
+  pub mod_id: String,
+  pub mod_name: String,
+  pub mod_path: String,
+  pub executable_dir: PathBuf,
+  pub session_id: String,
+  pub started_at: SystemTime,
+}
+
+/// Create an empty stderr tail for a new session
+pub fn new_stderr_tail() -> StderrTail {
+  Arc::new(Mutex::new(VecDeque::with_capacity(LAST_STDERR_LINES)))
+}
+
+/// Remember a stderr line, dropping the oldest one if needed
+pub fn push_stderr_line(tail: &StderrTail, line: &str) {
+  if let Ok(mut lines) = tail.lock() {
+    if lines.len() >= LAST_STDERR_LINES {
+      lines.pop_front();
+    }
+    lines.push_back(line.to_string());
+  }
+}
+
+/// Mark a session as stopped by the user, so its exit isn't reported as a crash
+pub fn mark_stop_requested(session_id: &str) {
+  STOP_REQUESTED.lock().unwrap().insert(session_id.to_string());
+}
+
+fn take_stop_requested(session_id: &str) -> bool {
+  STOP_REQUESTED.lock().unwrap().remove(session_id)
+}
+
+/// Check whether a mod crashed, and if so collect its crash files and report it.
+/// Returns true if the session crashed
+pub fn handle_session_exit(
+  context: &CrashContext,
+  exit_code: Option<i32>,
+  stderr_tail: &StderrTail
+) -> bool {
+  if take_stop_requested(&context.session_id) {
+    debug!("Session {} was stopped by the user", context.session_id);
+    return false;
+  }
+
+  let roots = search_roots(context);
+  let crash_files = find_new_files(&roots, &CRASH_DIRS, true, context.started_at);
+
+  if exit_code == Some(0) && crash_files.is_empty() {
+    return false;
+  }
+
+  let last_stderr = stderr_tail
+    .lock()
+    .map(|lines| lines.iter().cloned().collect::<Vec<_>>())
+    .unwrap_or_default();
+  let summary = build_summary(&crash_files, &last_stderr, exit_code);
+  info!("Mod {} crashed: {}", context.mod_name, summary);
+
+  // Keep logs written during the session along with the crash dumps
+  let mut files = crash_files;
+  files.extend(find_new_files(&roots, &LOG_DIRS, false, context.started_at));
+  let collected = collect_files(context, &files);
+
+  crate::terminaloutput::add_session_log(
+    &context.mod_id,
+    &context.session_id,
+    &format!("[ERROR] Mod crashed: {}", summary)
+  );
+  for file in &files {
+    crate::terminaloutput::add_session_log(
+      &context.mod_id,
+      &context.session_id,
+      &format!("[ERROR] Crash file: {}", file.display())
+    );
+  }
+
+  crate::terminaloutput::update_session_info(
+    &context.mod_id,
+    &context.session_id,
+    |session_info| {
+      session_info.crashed = true;
+      session_info.crash_summary = Some(summary.clone());
+      session_info.crash_files = collected.clone();
+    }
+  );
+
+  let payload = ModCrashed {
+    mod_id: context.mod_id.clone(),
+    session_id: context.session_id.clone(),
+    exit_code,
+    summary,
+    last_stderr,
+    crash_files: files
+      .iter()
+      .map(|f| f.to_string_lossy().to_string())
+      .collect(),
+  };
+  if let Err(e) = crate::app_handle::emit_event("mod-crashed", payload) {
+    error!("Failed to emit mod-crashed event: {}", e);
+  }
+
+  true
+}
+
+// The exe folder and the mod folder, without duplicates
+fn search_roots(context: &CrashContext) -> Vec<PathBuf> {
+  let mut roots = vec![context.executable_dir.clone()];
+  let mod_path = PathBuf::from(&context.mod_path);
+  if mod_path.is_dir() && !roots.contains(&mod_path) {
+    roots.push(mod_path);
+  }
+  roots
+}
+
+// Files in the given subfolders modified since the session started
+fn find_new_files(
+  roots: &[PathBuf],
+  dir_names: &[&str],
+  include_root_crash_files: bool,
+  since: SystemTime
+) -> Vec<PathBuf> {
+  let mut files = Vec::new();
+
+  for root in roots {
+    for dir_name in dir_names {
+      let dir = root.join(dir_name);
+      if let Ok(entries) = std::fs::read_dir(&dir) {
+        files.extend(
+          entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| is_modified_since(p, since))
+        );
+      }
+    }
+
+    // Some engines drop a crash log right next to the exe
+    if include_root_crash_files {
+      if let Ok(entries) = std::fs::read_dir(root) {
+        files.extend(
+          entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+              p.file_name()
+                .map(|n| n.to_string_lossy().to_lowercase().contains("crash"))
+                .unwrap_or(false)
+            })
+            .filter(|p| is_modified_since(p, since))
+        );
+      }
+    }
+  }
+
+  files.sort();
+  files.dedup();
+  files
+}
+
+fn is_modified_since(path: &Path, since: SystemTime) -> bool {
+  path.is_file() &&
+    std::fs
+      ::metadata(path)
+      .and_then(|m| m.modified())
+      .map(|modified| modified >= since)
+      .unwrap_or(false)
+}
+
+// Copy crash files into the session folder, returns the names of the copied files
+fn collect_files(context: &CrashContext, files: &[PathBuf]) -> Vec<String> {
+  let session_dir = match
+    crate::terminaloutput::get_session_dir(&context.mod_id, &context.session_id)
+  {
+    Some(dir) => dir.join("crash"),
+    None => {
+      debug!(
+        "No session folder for session {}, not collecting crash files",
+        context.session_id
+      );
+      return Vec::new();
+    }
+  };
+
+  if let Err(e) = std::fs::create_dir_all(&session_dir) {
+    error!("Failed to create crash folder {}: {}", session_dir.display(), e);
+    return Vec::new();
+  }
+
+  let mut collected = Vec::new();
+  for file in files {
+    let size = std::fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+    if size > MAX_COLLECTED_FILE_SIZE {
+      warn!("Not collecting {} ({} bytes), it's too big", file.display(), size);
+      continue;
+    }
+
+    let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+    let mut target = session_dir.join(file_name.as_ref());
+    // Crash and log folders can contain files with the same name
+    if target.exists() {
+      target = session_dir.join(format!("{}_{}", collected.len(), file_name));
+    }
+
+    match std::fs::copy(file, &target) {
+      Ok(_) => {
+        debug!("Collected crash file {} into {}", file.display(), target.display());
+        collected.push(
+          target.file_name().unwrap_or_default().to_string_lossy().to_string()
+        );
+      }
+      Err(e) => {
+        warn!("Failed to collect crash file {}: {}", file.display(), e);
+      }
+    }
+  }
+
+  collected
+}
+
+// Pick the most useful line to describe a crash
+fn build_summary(
+  crash_files: &[PathBuf],
+  last_stderr: &[String],
+  exit_code: Option<i32>
+) -> String {
+  // Haxe crash dumps usually have the error message near the top
+  for file in crash_files {
+    if let Ok(content) = std::fs::read_to_string(file) {
+      let error_line = content
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| {
+          let lower = l.to_lowercase();
+          lower.contains("error") || lower.contains("exception")
+        });
+      if let Some(line) = error_line {
+        return line.to_string();
+      }
+    }
+  }
+
+  if let Some(line) = last_stderr.iter().rev().find(|l| !l.trim().is_empty()) {
+    return line.trim().to_string();
+  }
+
+  match exit_code {
+    Some(code) => format!("Process exited with code {}", code),
+    None => "Process was terminated by a signal".to_string(),
+  }
+}
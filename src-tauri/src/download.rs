@@ -1,6 +1,6 @@
 use crate::filesystem::{
   check_for_custom_images,
-  find_executables,
+  find_executable_candidates,
   extract_executable_icon,
 };
 use crate::gamebanana::{
@@ -9,24 +9,71 @@ use crate::gamebanana::{
   extract_banner_url,
   extract_contributors,
 };
+use crate::downloadqueue::{
+  CancelToken,
+  EmitDownloadEvent,
+  DOWNLOAD_CANCELLED,
+  DOWNLOAD_PAUSED,
+};
 use crate::models::{
+  DownloadCancelled,
   DownloadError,
   DownloadFinished,
   DownloadProgress,
   DownloadStarted,
   GBFile,
   GBProfilePage,
+  LaunchHooks,
+  LaunchProfile,
   ModInfo,
   CURRENT_METADATA_VERSION,
 };
 use crate::utils::{ fetch_image_as_base64, extract_rar_archive };
-use futures_util::StreamExt;
 use log::{ debug, error, info, warn };
+use md5::{ Digest, Md5 };
 use serde::{ Deserialize, Serialize };
 use tauri::path::BaseDirectory;
 use std::fs;
 use std::path::{ Path, PathBuf };
-use tauri::{ Manager, Emitter };
+use tauri::Manager;
+
+// Error code of downloads that don't match the checksum GameBanana gave us
+pub const CHECKSUM_MISMATCH_ERROR: &str = "checksum_mismatch";
+
+// Clean up what a cancelled download left behind and let the frontend know
+fn finish_cancelled_download(
+  app: &tauri::AppHandle,
+  download_id: i64,
+  name: &str,
+  archive_path: Option<&Path>,
+  created_folder: Option<&Path>
+) -> String {
+  info!("Download of {} was cancelled", name);
+
+  if let Some(archive_path) = archive_path {
+    if archive_path.exists() {
+      if let Err(e) = fs::remove_file(archive_path) {
+        warn!("Failed to delete archive of cancelled download: {}", e);
+      }
+    }
+  }
+  // Only folders this download made, an update extracts over the existing one
+  if let Some(folder) = created_folder {
+    if let Err(e) = fs::remove_dir_all(folder) {
+      warn!("Failed to delete folder of cancelled download: {}", e);
+    }
+  }
+
+  app
+    .emit_download("download-cancelled", DownloadCancelled {
+      mod_id: download_id,
+      name: name.to_string(),
+    })
+    .unwrap_or_else(|e|
+      error!("Failed to emit download-cancelled event: {}", e)
+    );
+  DOWNLOAD_CANCELLED.to_string()
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 struct EngineConfig {
@@ -47,6 +94,7 @@ pub async fn download_gamebanana_mod(
   install_location: Option<String>,
   folder_name: Option<String>,
   update_existing: Option<bool>,
+  cancel_token: CancelToken,
   app: tauri::AppHandle
 ) -> Result<ModInfo, String> {
   info!(
@@ -64,7 +112,7 @@ pub async fn download_gamebanana_mod(
 
   // Emit download started event
   app
-    .emit("download-started", DownloadStarted {
+    .emit_download("download-started", DownloadStarted {
       mod_id: file_id,
       name: info.name.clone(),
       content_length: file.filesize as usize,
@@ -93,22 +141,23 @@ pub async fn download_gamebanana_mod(
     None
   };
 
-  // Get the download folder
-  let downloads_dir = match app.path().download_dir() {
+  // Downloads are staged in the app cache until they're extracted
+  let staging_dir = match crate::downloadcache::get_staging_dir(&app) {
     Ok(path) => {
-      debug!("Download directory: {}", path.display());
+      debug!("Download cache directory: {}", path.display());
       path
     }
     Err(e) => {
-      let error_msg = format!("Failed to find downloads directory: {}", e);
+      let error_msg = format!("Failed to find download cache: {}", e);
       error!("{}", error_msg);
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
-          mod_id,
+        .emit_download("download-error", DownloadError {
+          mod_id: file_id,
           name: info.name.clone(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -118,35 +167,92 @@ pub async fn download_gamebanana_mod(
     }
   };
 
-  // Download the file with progress tracking
-  debug!("Sending HTTP request to download mod");
-
-  // Use reqwest to perform download with progress tracking
-  let client = reqwest::Client::new();
-  let response = match client.get(&file.download_url).send().await {
-    Ok(resp) => {
-      debug!("Received response with status: {}", resp.status());
-      if !resp.status().is_success() {
-        let err_msg = format!(
-          "Server returned error status: {}",
-          resp.status()
+  // Download the file with progress tracking, resuming an earlier attempt if there is one
+  debug!("Downloading mod from {}", file.download_url);
+  let partial_path = crate::partialdownloads::get_partial_path(
+    &staging_dir,
+    &format!("gamebanana-{}", file_id)
+  );
+  let partial_in_use = match crate::downloadcache::claim_partial(
+    file_id,
+    &partial_path
+  ) {
+    Some(guard) => guard,
+    None => {
+      let error_msg = format!("{} is already being downloaded", info.name);
+      error!("{}", error_msg);
+
+      // Emit error event
+      app
+        .emit_download("download-error", DownloadError {
+          mod_id: file_id,
+          name: info.name.clone(),
+          error: error_msg.clone(),
+          error_code: None,
+        })
+        .unwrap_or_else(|e|
+          error!("Failed to emit download-error event: {}", e)
         );
-        error!("{}", err_msg);
 
-        // Emit error event
+      return Err(error_msg);
+    }
+  };
+  let mut last_percentage = 0;
+  let download_result = crate::partialdownloads::download(
+    &file.download_url,
+    &partial_path,
+    &cancel_token,
+    |total_size| {
+      crate::downloadcache::make_room_for_download(&app, &partial_path, total_size);
+
+      // Update the download started event with actual content length
+      app
+        .emit_download("download-started", DownloadStarted {
+          mod_id: file_id,
+          name: info.name.clone(),
+          content_length: total_size,
+          thumbnail_url: None,
+        })
+        .unwrap_or_else(|e|
+          error!("Failed to emit updated download-started event: {}", e)
+        );
+    },
+    |downloaded, total_size| {
+      let percentage = if total_size > 0 {
+        ((((downloaded as f64) / (total_size as f64)) * 60.0) as u8) + 20 // 20-80% range for download
+      } else {
+        30 // Default to middle of range if size unknown
+      };
+
+      // Only emit progress events if percentage has changed
+      if percentage != last_percentage {
         app
-          .emit("download-error", DownloadError {
-            mod_id,
+          .emit_download("download-progress", DownloadProgress {
+            mod_id: file_id,
             name: info.name.clone(),
-            error: err_msg.clone(),
+            bytes_downloaded: downloaded,
+            total_bytes: total_size,
+            percentage,
+            step_key: "app.notifications.download.downloading_nonspecific".to_string(),
+            step_variables: None,
           })
           .unwrap_or_else(|e|
-            error!("Failed to emit download-error event: {}", e)
+            error!("Failed to emit download-progress event: {}", e)
           );
 
-        return Err(err_msg);
+        last_percentage = percentage;
       }
-      resp
+    }
+  ).await;
+  let completed_download = match download_result {
+    Ok(completed_download) => completed_download,
+    Err(_) if cancel_token.is_cancelled() => {
+      return Err(finish_cancelled_download(&app, file_id, &info.name, None, None));
+    }
+    // Picked up again when the download is resumed
+    Err(e) if e == DOWNLOAD_PAUSED => {
+      crate::downloadcache::keep_while_paused(file_id, partial_in_use);
+      return Err(e);
     }
     Err(e) => {
       let error_msg = format!("Failed to download mod: {}", e);
@@ -154,10 +260,11 @@ pub async fn download_gamebanana_mod(
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
-          mod_id,
+        .emit_download("download-error", DownloadError {
+          mod_id: file_id,
           name: info.name.clone(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -166,25 +273,24 @@ pub async fn download_gamebanana_mod(
       return Err(error_msg);
     }
   };
+  let total_size = completed_download.total_bytes;
 
   // Create a unique filename with appropriate extension based on Content-Type header
-  let extension = response
-    .headers()
-    .get(reqwest::header::CONTENT_TYPE)
-    .and_then(|ct| ct.to_str().ok())
-    .and_then(|ct| {
+  let extension = completed_download.content_type
+    .as_deref()
+    .map(|ct| {
       if ct.contains("application/zip") || ct.contains("application/x-zip") {
-        Some("zip")
+        "zip"
       } else if ct.contains("application/x-7z-compressed") {
-        Some("7z")
+        "7z"
       } else if
         ct.contains("application/x-rar-compressed") ||
         ct.contains("application/vnd.rar")
       {
-        Some("rar")
+        "rar"
       } else {
         // Default to zip if unknown
-        Some("zip")
+        "zip"
       }
     })
     .unwrap_or("zip");
@@ -195,125 +301,84 @@ pub async fn download_gamebanana_mod(
     chrono::Utc::now().timestamp(),
     extension
   );
-  let download_path = downloads_dir.join(&filename);
+  let download_path = staging_dir.join(&filename);
+  let _archive_in_use = crate::downloadcache::mark_in_use(&download_path);
 
   debug!("Download path: {}", download_path.display());
 
-  // Get the content length for progress tracking
-  let total_size = response.content_length().unwrap_or(0) as usize;
-  // Update the download started event with actual content length
-  app
-    .emit("download-started", DownloadStarted {
-      mod_id,
-      name: info.name.clone(),
-      content_length: total_size,
-      thumbnail_url: None,
-    })
-    .unwrap_or_else(|e|
-      error!("Failed to emit updated download-started event: {}", e)
+  // Make sure the archive isn't truncated or corrupted before extracting it
+  let expected_checksum = file.md5_checksum.trim();
+  if expected_checksum.is_empty() {
+    warn!("GameBanana didn't give a checksum, skipping verification");
+  } else if !completed_download.md5.eq_ignore_ascii_case(expected_checksum) {
+    let error_msg = format!(
+      "Downloaded file is corrupted, expected MD5 {} but got {}",
+      expected_checksum,
+      completed_download.md5
     );
+    error!("{}", error_msg);
 
-  // Create a file to write to
-  let mut file = match std::fs::File::create(&download_path) {
-    Ok(file) => file,
-    Err(e) => {
-      let error_msg = format!("Failed to create file: {}", e);
-      error!("{}", error_msg);
-
-      // Emit error event
-      app
-        .emit("download-error", DownloadError {
-          mod_id,
-          name: info.name.clone(),
-          error: error_msg.clone(),
-        })
-        .unwrap_or_else(|e|
-          error!("Failed to emit download-error event: {}", e)
-        );
-
-      return Err(error_msg);
-    }
-  };
-
-  // Stream the response body with progress updates
-  let mut stream = response.bytes_stream();
-  let mut downloaded: usize = 0;
-  let mut last_percentage = 0;
-
-  while let Some(chunk_result) = stream.next().await {
-    match chunk_result {
-      Ok(chunk) => {
-        // chunk is of type reqwest::Bytes here
-        // Write the chunk to the file
-        // &chunk dereferences Bytes to &[u8] for write_all
-        if let Err(e) = std::io::Write::write_all(&mut file, &chunk) {
-          let error_msg = format!("Failed to write to file: {}", e);
-          error!("{}", error_msg);
-
-          // Emit error event
-          app
-            .emit("download-error", DownloadError {
-              mod_id,
-              name: info.name.clone(),
-              error: error_msg.clone(),
-            })
-            .unwrap_or_else(|e|
-              error!("Failed to emit download-error event: {}", e)
-            );
-
-          return Err(error_msg);
+    // Kept where the user can look at it, the cache gets cleaned up
+    if crate::downloadsettings::get_settings().keep_corrupted_downloads {
+      let kept_result = app
+        .path()
+        .download_dir()
+        .map_err(|e| format!("Failed to find downloads directory: {}", e))
+        .and_then(|dir| {
+          let kept_path = dir.join(&filename);
+          crate::partialdownloads
+            ::finish(&completed_download, &kept_path)
+            .map(|_| kept_path)
+        });
+      match kept_result {
+        Ok(kept_path) => info!("Keeping corrupted download at {}", kept_path.display()),
+        Err(e) => {
+          warn!("Failed to keep corrupted download: {}", e);
+          crate::partialdownloads::discard(&completed_download);
         }
+      }
+    } else {
+      crate::partialdownloads::discard(&completed_download);
+    }
 
-        // Update progress
-        downloaded += chunk.len();
-        let percentage = if total_size > 0 {
-          ((((downloaded as f64) / (total_size as f64)) * 60.0) as u8) + 20 // 20-80% range for download
-        } else {
-          30 // Default to middle of range if size unknown
-        };
+    // Emit error event
+    app
+      .emit_download("download-error", DownloadError {
+        mod_id: file_id,
+        name: info.name.clone(),
+        error: error_msg.clone(),
+        error_code: Some(CHECKSUM_MISMATCH_ERROR.to_string()),
+      })
+      .unwrap_or_else(|e|
+        error!("Failed to emit download-error event: {}", e)
+      );
 
-        // Only emit progress events if percentage has changed
-        if percentage != last_percentage {
-          app
-            .emit("download-progress", DownloadProgress {
-              mod_id: file_id,
-              name: info.name.clone(),
-              bytes_downloaded: downloaded,
-              total_bytes: total_size,
-              percentage,
-              step_key: "app.notifications.download.downloading_nonspecific".to_string(),
-              step_variables: None,
-            })
-            .unwrap_or_else(|e|
-              error!("Failed to emit download-progress event: {}", e)
-            );
+    return Err(error_msg);
+  } else {
+    debug!("Download matches MD5 checksum {}", expected_checksum);
+  }
 
-          last_percentage = percentage;
-        }
-      }
-      Err(e) => {
-        let error_msg = format!("Failed to download chunk: {}", e);
-        error!("{}", error_msg);
+  if let Err(e) = crate::partialdownloads::finish(&completed_download, &download_path) {
+    error!("{}", e);
 
-        // Emit error event
-        app
-          .emit("download-error", DownloadError {
-            mod_id,
-            name: info.name.clone(),
-            error: error_msg.clone(),
-          })
-          .unwrap_or_else(|e|
-            error!("Failed to emit download-error event: {}", e)
-          );
+    // Emit error event
+    app
+      .emit_download("download-error", DownloadError {
+        mod_id: file_id,
+        name: info.name.clone(),
+        error: e.clone(),
+        error_code: None,
+      })
+      .unwrap_or_else(|e|
+        error!("Failed to emit download-error event: {}", e)
+      );
 
-        return Err(error_msg);
-      }
-    }
+    return Err(e);
   }
 
   // Emit progress event for extraction
   app
-    .emit("download-progress", DownloadProgress {
+    .emit_download("download-progress", DownloadProgress {
       mod_id: file_id,
       name: info.name.clone(),
       bytes_downloaded: total_size,
@@ -343,10 +408,11 @@ pub async fn download_gamebanana_mod(
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
-          mod_id,
+        .emit_download("download-error", DownloadError {
+          mod_id: file_id,
           name: info.name.clone(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -388,13 +454,14 @@ pub async fn download_gamebanana_mod(
       // When updating, we keep the existing folder and just extract over it
     } else {
       app
-        .emit("download-error", DownloadError {
-          mod_id,
+        .emit_download("download-error", DownloadError {
+          mod_id: file_id,
           name: info.name.clone(),
           error: format!(
             "Tried to install to an existing mod folder: {}",
             mod_folder.display()
           ),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -408,6 +475,7 @@ pub async fn download_gamebanana_mod(
     }
   }
 
+  let created_mod_folder = !mod_folder.exists();
   debug!("Creating mod folder: {}", mod_folder.display());
   if let Err(e) = fs::create_dir_all(&mod_folder) {
     let error_msg = format!("Failed to create mod folder: {}", e);
@@ -415,10 +483,11 @@ pub async fn download_gamebanana_mod(
 
     // Emit error event
     app
-      .emit("download-error", DownloadError {
-        mod_id,
+      .emit_download("download-error", DownloadError {
+        mod_id: file_id,
         name: info.name.clone(),
         error: error_msg.clone(),
+        error_code: None,
       })
       .unwrap_or_else(|e| error!("Failed to emit download-error event: {}", e));
 
@@ -430,10 +499,22 @@ pub async fn download_gamebanana_mod(
     &download_path,
     &mod_folder,
     &info.name,
-    mod_id,
+    file_id,
+    &cancel_token,
     &app
   );
   if let Err(e) = extraction_result {
+    if cancel_token.is_cancelled() {
+      return Err(
+        finish_cancelled_download(
+          &app,
+          file_id,
+          &info.name,
+          Some(&download_path),
+          created_mod_folder.then_some(mod_folder.as_path())
+        )
+      );
+    }
     return Err(e);
   }
 
@@ -445,7 +526,7 @@ pub async fn download_gamebanana_mod(
 
   // Emit progress event for finalizing
   app
-    .emit("download-progress", DownloadProgress {
+    .emit_download("download-progress", DownloadProgress {
       mod_id: file_id,
       name: info.name.clone(),
       bytes_downloaded: 95,
@@ -460,10 +541,10 @@ pub async fn download_gamebanana_mod(
 
   // Find executable in the extracted files
   debug!("Searching for executables in mod folder");
-  let executables = find_executables(&mod_folder);
-  let executable_path = executables
+  let executable_candidates = find_executable_candidates(&mod_folder);
+  let executable_path = executable_candidates
     .first()
-    .map(|p| p.to_string_lossy().to_string());
+    .map(|c| c.path.clone());
 
   // Extract icon if we have an executable
   let icon_data = match &executable_path {
@@ -490,6 +571,7 @@ pub async fn download_gamebanana_mod(
     name: info.name.clone(),
     path: mod_folder.to_string_lossy().to_string(),
     executable_path,
+    executable_candidates,
     display_order: Some(0),
     icon_data,
     description: info.description.clone(),
@@ -511,6 +593,9 @@ pub async fn download_gamebanana_mod(
     save_terminal_output: false, // Default to false
     current_session_id: None, // Initialize with None since mod is not running yet
     session_start_time: None, // Initialize with None since mod is not running yet
+    runner_id: None, // Use the default runner
+    launch_profile: LaunchProfile::default(),
+    hooks: LaunchHooks::default(),
   };
 
   // Create metadata.json file in the .flight folder
@@ -578,10 +663,11 @@ pub async fn download_gamebanana_mod(
       warn!("{}", err_msg);
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id: file_id,
           name: info.name.clone(),
           error: err_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -599,7 +685,7 @@ pub async fn download_gamebanana_mod(
 
   // Emit download finished event
   app
-    .emit("download-finished", DownloadFinished {
+    .emit_download("download-finished", DownloadFinished {
       mod_id: file_id,
       name: info.name.clone(),
       mod_info: mod_info.clone(),
@@ -610,7 +696,7 @@ pub async fn download_gamebanana_mod(
 
   // Emit progress event for completion
   app
-    .emit("download-progress", DownloadProgress {
+    .emit_download("download-progress", DownloadProgress {
       mod_id: file_id,
       name: info.name.clone(),
       bytes_downloaded: 100,
@@ -784,6 +870,7 @@ pub async fn download_engine(
   custom_name: Option<String>,
   update_existing: Option<bool>,
   download_id: i64,
+  cancel_token: CancelToken,
   app: tauri::AppHandle
 ) -> Result<ModInfo, String> {
   info!("Starting direct engine download for: {}", engine_id);
@@ -802,7 +889,7 @@ pub async fn download_engine(
 
   // Emit download started event
   app
-    .emit("download-started", DownloadStarted {
+    .emit_download("download-started", DownloadStarted {
       mod_id: download_id,
       name: engine_name.to_string(),
       content_length: 0, // We don't know the size yet
@@ -812,7 +899,7 @@ pub async fn download_engine(
 
   // Emit progress event for the engine download fetch step
   app
-    .emit("download-progress", DownloadProgress {
+    .emit_download("download-progress", DownloadProgress {
       mod_id: download_id,
       name: engine_name.to_string(),
       percentage: 5,
@@ -825,22 +912,23 @@ pub async fn download_engine(
       error!("Failed to emit download-progress event: {}", e)
     );
 
-  // Get the download folder
-  let downloads_dir = match app.path().download_dir() {
+  // Downloads are staged in the app cache until they're extracted
+  let staging_dir = match crate::downloadcache::get_staging_dir(&app) {
     Ok(path) => {
-      debug!("Download directory: {}", path.display());
+      debug!("Download cache directory: {}", path.display());
       path
     }
     Err(e) => {
-      let error_msg = format!("Failed to find downloads directory: {}", e);
+      let error_msg = format!("Failed to find download cache: {}", e);
       error!("{}", error_msg);
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id: download_id,
           name: engine_name.to_string(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -850,35 +938,134 @@ pub async fn download_engine(
     }
   };
 
-  // Download the file with progress tracking
-  debug!("Sending HTTP request to download engine");
-
-  // Use reqwest to perform download with progress tracking
-  let client = reqwest::Client::new();
-  let response = match client.get(&engine_url).send().await {
-    Ok(resp) => {
-      debug!("Received response with status: {}", resp.status());
-      if !resp.status().is_success() {
-        let err_msg = format!(
-          "Server returned error status: {}",
-          resp.status()
+  // Get the install location - use provided location or fall back to default
+  let install_dir = if let Some(location) = install_location {
+    let path = PathBuf::from(&location);
+    info!("Using provided install location: {}", path.display());
+    path
+  } else {
+    let default_path = get_default_install_location(&app);
+    info!("Using default install location: {}", default_path.display());
+    default_path
+  };
+
+  debug!("Using install location: {}", install_dir.display());
+
+  // Use the custom name if provided, otherwise use the default engine name
+  let folder_name = match custom_name {
+    Some(name) => {
+      info!("Using custom name for engine folder: {}", name);
+      name
+    }
+    None => {
+      // Create sanitized name from engine name as before
+      let sanitized_name = engine_name.replace(' ', "-");
+      info!("Using default name for engine folder: {}", sanitized_name);
+      sanitized_name
+    }
+  };
+
+  // Create engine folder with the determined name
+  let engine_folder = install_dir.join(&folder_name);
+
+  // Download the file with progress tracking, resuming an earlier attempt if there is one
+  debug!("Downloading engine from {}", engine_url);
+  // The same engine can be downloaded into several folders at once, so the
+  // partial file goes with the folder it's installed to
+  let partial_path = crate::partialdownloads::get_partial_path(
+    &staging_dir,
+    &format!(
+      "engine-{}-{:x}",
+      engine_id,
+      Md5::digest(engine_folder.to_string_lossy().as_bytes())
+    )
+  );
+  let partial_in_use = match crate::downloadcache::claim_partial(
+    download_id,
+    &partial_path
+  ) {
+    Some(guard) => guard,
+    None => {
+      let error_msg = format!(
+        "{} is already being downloaded to {}",
+        engine_name,
+        engine_folder.display()
+      );
+      error!("{}", error_msg);
+
+      // Emit error event
+      app
+        .emit_download("download-error", DownloadError {
+          mod_id: download_id,
+          name: engine_name.to_string(),
+          error: error_msg.clone(),
+          error_code: None,
+        })
+        .unwrap_or_else(|e|
+          error!("Failed to emit download-error event: {}", e)
         );
-        error!("{}", err_msg);
 
-        // Emit error event
+      return Err(error_msg);
+    }
+  };
+  let mut last_percentage = 0;
+  let download_result = crate::partialdownloads::download(
+    &engine_url,
+    &partial_path,
+    &cancel_token,
+    |total_size| {
+      crate::downloadcache::make_room_for_download(&app, &partial_path, total_size);
+
+      // Update the download started event with actual content length
+      app
+        .emit_download("download-started", DownloadStarted {
+          mod_id: download_id,
+          name: engine_name.to_string(),
+          content_length: total_size,
+          thumbnail_url: None,
+        })
+        .unwrap_or_else(|e|
+          error!("Failed to emit updated download-started event: {}", e)
+        );
+    },
+    |downloaded, total_size| {
+      let percentage = if total_size > 0 {
+        ((((downloaded as f64) / (total_size as f64)) * 60.0) as u8) + 20 // 20-80% range for download
+      } else {
+        30 // Default to middle of range if size unknown
+      };
+
+      // Only emit progress events if percentage has changed
+      if percentage != last_percentage {
         app
-          .emit("download-error", DownloadError {
+          .emit_download("download-progress", DownloadProgress {
             mod_id: download_id,
             name: engine_name.to_string(),
-            error: err_msg.clone(),
+            bytes_downloaded: downloaded,
+            total_bytes: total_size,
+            percentage,
+            step_key: "app.notifications.download.downloading_engine_nonspecific".to_string(),
+            step_variables: None,
           })
           .unwrap_or_else(|e|
-            error!("Failed to emit download-error event: {}", e)
+            error!("Failed to emit download-progress event: {}", e)
           );
 
-        return Err(err_msg);
+        last_percentage = percentage;
       }
-      resp
+    }
+  ).await;
+  let completed_download = match download_result {
+    Ok(completed_download) => completed_download,
+    Err(_) if cancel_token.is_cancelled() => {
+      return Err(
+        finish_cancelled_download(&app, download_id, &engine_name, None, None)
+      );
+    }
+    // Picked up again when the download is resumed
+    Err(e) if e == DOWNLOAD_PAUSED => {
+      crate::downloadcache::keep_while_paused(download_id, partial_in_use);
+      return Err(e);
     }
     Err(e) => {
       let error_msg = format!("Failed to download engine: {}", e);
@@ -886,10 +1073,11 @@ pub async fn download_engine(
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id: download_id,
           name: engine_name.to_string(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -898,12 +1086,11 @@ pub async fn download_engine(
       return Err(error_msg);
     }
   };
+  let total_size = completed_download.total_bytes;
 
   // Detect archive type from Content-Type header or URL
-  let extension = response
-    .headers()
-    .get(reqwest::header::CONTENT_TYPE)
-    .and_then(|ct| ct.to_str().ok())
+  let extension = completed_download.content_type
+    .as_deref()
     .and_then(|ct| {
       if ct.contains("application/zip") || ct.contains("application/x-zip") {
         Some("zip")
@@ -939,124 +1126,32 @@ pub async fn download_engine(
     chrono::Utc::now().timestamp(),
     extension
   );
-  let download_path = downloads_dir.join(&filename);
+  let download_path = staging_dir.join(&filename);
+  let _archive_in_use = crate::downloadcache::mark_in_use(&download_path);
 
   debug!("Download path: {}", download_path.display());
 
-  // Get the content length for progress tracking
-  let total_size = response.content_length().unwrap_or(0) as usize;
-
-  // Update the download started event with actual content length
-  app
-    .emit("download-started", DownloadStarted {
-      mod_id: download_id,
-      name: engine_name.to_string(),
-      content_length: total_size,
-      thumbnail_url: None,
-    })
-    .unwrap_or_else(|e|
-      error!("Failed to emit updated download-started event: {}", e)
-    );
-
-  // Create a file to write to
-  let mut file = match std::fs::File::create(&download_path) {
-    Ok(file) => file,
-    Err(e) => {
-      let error_msg = format!("Failed to create file: {}", e);
-      error!("{}", error_msg);
-
-      // Emit error event
-      app
-        .emit("download-error", DownloadError {
-          mod_id: download_id,
-          name: engine_name.to_string(),
-          error: error_msg.clone(),
-        })
-        .unwrap_or_else(|e|
-          error!("Failed to emit download-error event: {}", e)
-        );
-
-      return Err(error_msg);
-    }
-  };
-
-  // Stream the response body with progress updates
-  let mut stream = response.bytes_stream();
-  let mut downloaded: usize = 0;
-  let mut last_percentage = 0;
-
-  while let Some(chunk_result) = stream.next().await {
-    match chunk_result {
-      Ok(chunk) => {
-        // Write the chunk to the file
-        if let Err(e) = std::io::Write::write_all(&mut file, &chunk) {
-          let error_msg = format!("Failed to write to file: {}", e);
-          error!("{}", error_msg);
-
-          // Emit error event
-          app
-            .emit("download-error", DownloadError {
-              mod_id: download_id,
-              name: engine_name.to_string(),
-              error: error_msg.clone(),
-            })
-            .unwrap_or_else(|e|
-              error!("Failed to emit download-error event: {}", e)
-            );
-
-          return Err(error_msg);
-        }
-
-        // Update progress
-        downloaded += chunk.len();
-        let percentage = if total_size > 0 {
-          ((((downloaded as f64) / (total_size as f64)) * 60.0) as u8) + 20 // 20-80% range for download
-        } else {
-          30 // Default to middle of range if size unknown
-        };
-
-        // Only emit progress events if percentage has changed
-        if percentage != last_percentage {
-          app
-            .emit("download-progress", DownloadProgress {
-              mod_id: download_id,
-              name: engine_name.to_string(),
-              bytes_downloaded: downloaded,
-              total_bytes: total_size,
-              percentage,
-              step_key: "app.notifications.download.downloading_engine_nonspecific".to_string(),
-              step_variables: None,
-            })
-            .unwrap_or_else(|e|
-              error!("Failed to emit download-progress event: {}", e)
-            );
-
-          last_percentage = percentage;
-        }
-      }
-      Err(e) => {
-        let error_msg = format!("Failed to download chunk: {}", e);
-        error!("{}", error_msg);
+  if let Err(e) = crate::partialdownloads::finish(&completed_download, &download_path) {
+    error!("{}", e);
 
-        // Emit error event
-        app
-          .emit("download-error", DownloadError {
-            mod_id: download_id,
-            name: engine_name.to_string(),
-            error: error_msg.clone(),
-          })
-          .unwrap_or_else(|e|
-            error!("Failed to emit download-error event: {}", e)
-          );
+    // Emit error event
+    app
+      .emit_download("download-error", DownloadError {
+        mod_id: download_id,
+        name: engine_name.to_string(),
+        error: e.clone(),
+        error_code: None,
+      })
+      .unwrap_or_else(|e|
+        error!("Failed to emit download-error event: {}", e)
+      );
 
-        return Err(error_msg);
-      }
-    }
+    return Err(e);
   }
 
   // Emit progress event for extraction
   app
-    .emit("download-progress", DownloadProgress {
+    .emit_download("download-progress", DownloadProgress {
       mod_id: download_id,
       name: engine_name.to_string(),
       bytes_downloaded: total_size,
@@ -1069,19 +1164,6 @@ pub async fn download_engine(
       error!("Failed to emit download-progress event: {}", e)
     );
 
-  // Get the install location - use provided location or fall back to default
-  let install_dir = if let Some(location) = install_location {
-    let path = PathBuf::from(&location);
-    info!("Using provided install location: {}", path.display());
-    path
-  } else {
-    let default_path = get_default_install_location(&app);
-    info!("Using default install location: {}", default_path.display());
-    default_path
-  };
-
-  debug!("Using install location: {}", install_dir.display());
-
   // Create the install directory if it doesn't exist
   if !install_dir.exists() {
     debug!("Creating install directory: {}", install_dir.display());
@@ -1091,10 +1173,11 @@ pub async fn download_engine(
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id: download_id,
           name: engine_name.to_string(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -1104,23 +1187,6 @@ pub async fn download_engine(
     }
   }
 
-  // Use the custom name if provided, otherwise use the default engine name
-  let folder_name = match custom_name {
-    Some(name) => {
-      info!("Using custom name for engine folder: {}", name);
-      name
-    }
-    None => {
-      // Create sanitized name from engine name as before
-      let sanitized_name = engine_name.replace(' ', "-");
-      info!("Using default name for engine folder: {}", sanitized_name);
-      sanitized_name
-    }
-  };
-
-  // Create engine folder with the determined name
-  let engine_folder = install_dir.join(&folder_name);
-
   if engine_folder.exists() {
     if update_existing.unwrap_or(false) {
       debug!(
@@ -1141,10 +1207,11 @@ pub async fn download_engine(
 
         // Emit error event
         app
-          .emit("download-error", DownloadError {
+          .emit_download("download-error", DownloadError {
             mod_id: download_id,
             name: engine_name.to_string(),
             error: error_msg.clone(),
+            error_code: None,
           })
           .unwrap_or_else(|e|
             error!("Failed to emit download-error event: {}", e)
@@ -1156,7 +1223,8 @@ pub async fn download_engine(
   }
 
   // Only create the directory if it doesn't exist (for updates, it should already exist)
-  if !engine_folder.exists() {
+  let created_engine_folder = !engine_folder.exists();
+  if created_engine_folder {
     debug!("Creating engine folder: {}", engine_folder.display());
     if let Err(e) = fs::create_dir_all(&engine_folder) {
       let error_msg = format!("Failed to create engine folder: {}", e);
@@ -1164,10 +1232,11 @@ pub async fn download_engine(
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id: download_id,
           name: engine_name.to_string(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -1183,9 +1252,21 @@ pub async fn download_engine(
     &engine_folder,
     &engine_name,
     download_id,
+    &cancel_token,
     &app
   );
   if let Err(e) = extraction_result {
+    if cancel_token.is_cancelled() {
+      return Err(
+        finish_cancelled_download(
+          &app,
+          download_id,
+          &engine_name,
+          Some(&download_path),
+          created_engine_folder.then_some(engine_folder.as_path())
+        )
+      );
+    }
     return Err(e);
   }
 
@@ -1197,7 +1278,7 @@ pub async fn download_engine(
 
   // Emit progress event for finalizing
   app
-    .emit("download-progress", DownloadProgress {
+    .emit_download("download-progress", DownloadProgress {
       mod_id: download_id,
       name: engine_name.to_string(),
       bytes_downloaded: 95,
@@ -1212,10 +1293,10 @@ pub async fn download_engine(
 
   // Find executable in the extracted files
   debug!("Searching for executables in engine folder");
-  let executables = find_executables(&engine_folder);
-  let executable_path = executables
+  let executable_candidates = find_executable_candidates(&engine_folder);
+  let executable_path = executable_candidates
     .first()
-    .map(|p| p.to_string_lossy().to_string());
+    .map(|c| c.path.clone());
 
   // Extract icon if we have an executable
   let icon_data = match &executable_path {
@@ -1325,6 +1406,7 @@ pub async fn download_engine(
     name: engine_name.to_string(),
     path: engine_folder.to_string_lossy().to_string(),
     executable_path,
+    executable_candidates,
     display_order: Some(0),
     icon_data: icon_data.clone(),
     description: Some(engine_description.to_string()),
@@ -1348,6 +1430,9 @@ pub async fn download_engine(
     save_terminal_output: false, // Default to false
     current_session_id: None, // Initialize with None since mod is not running yet
     session_start_time: None, // Initialize with None since mod is not running yet
+    runner_id: None, // Use the default runner
+    launch_profile: LaunchProfile::default(),
+    hooks: LaunchHooks::default(),
   };
 
   // Add the mod to our state
@@ -1372,10 +1457,11 @@ pub async fn download_engine(
       warn!("{}", err_msg);
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id: download_id,
           name: engine_name.to_string(),
           error: err_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -1390,7 +1476,7 @@ pub async fn download_engine(
 
   // Emit download finished event
   app
-    .emit("download-finished", DownloadFinished {
+    .emit_download("download-finished", DownloadFinished {
       mod_id: download_id,
       name: engine_name.to_string(),
       mod_info: mod_info.clone(),
@@ -1401,7 +1487,7 @@ pub async fn download_engine(
 
   // Emit progress event for completion
   app
-    .emit("download-progress", DownloadProgress {
+    .emit_download("download-progress", DownloadProgress {
       mod_id: download_id,
       name: engine_name.to_string(),
       bytes_downloaded: 100,
@@ -1417,27 +1503,193 @@ pub async fn download_engine(
   Ok(mod_info)
 }
 
+// Install an archive from the archive library again, to the folder it was first installed to
+pub async fn reinstall_archived_download(
+  file_name: String,
+  download_id: i64,
+  cancel_token: CancelToken,
+  app: tauri::AppHandle
+) -> Result<ModInfo, String> {
+  let archive = match crate::downloadcache::get_archive(&file_name) {
+    Ok(archive) => archive,
+    Err(e) => {
+      error!("{}", e);
+      app
+        .emit_download("download-error", DownloadError {
+          mod_id: download_id,
+          name: file_name.clone(),
+          error: e.clone(),
+          error_code: None,
+        })
+        .unwrap_or_else(|e| error!("Failed to emit download-error event: {}", e));
+      return Err(e);
+    }
+  };
+  let name = archive.name.clone().unwrap_or_else(|| file_name.clone());
+  info!("Reinstalling {} from archive {}", name, file_name);
+
+  app
+    .emit_download("download-started", DownloadStarted {
+      mod_id: download_id,
+      name: name.clone(),
+      content_length: archive.size as usize,
+      thumbnail_url: None,
+    })
+    .unwrap_or_else(|e| error!("Failed to emit download-started event: {}", e));
+
+  let emit_error = |error_msg: &str| {
+    error!("{}", error_msg);
+    app
+      .emit_download("download-error", DownloadError {
+        mod_id: download_id,
+        name: name.clone(),
+        error: error_msg.to_string(),
+        error_code: None,
+      })
+      .unwrap_or_else(|e| error!("Failed to emit download-error event: {}", e));
+  };
+
+  let mod_folder = match &archive.install_path {
+    Some(install_path) => PathBuf::from(install_path),
+    None => {
+      let error_msg = format!("No install folder was saved for archive {}", file_name);
+      emit_error(&error_msg);
+      return Err(error_msg);
+    }
+  };
+
+  // Extracting moves or deletes the archive, so work on a staged copy of it
+  let staged_path = match crate::downloadcache::get_staging_dir(&app) {
+    Ok(staging_dir) => staging_dir.join(&file_name),
+    Err(e) => {
+      emit_error(&e);
+      return Err(e);
+    }
+  };
+  let _archive_in_use = crate::downloadcache::mark_in_use(&staged_path);
+  if let Err(e) = fs::copy(&archive.path, &staged_path) {
+    let error_msg = format!("Failed to copy archive {}: {}", file_name, e);
+    emit_error(&error_msg);
+    return Err(error_msg);
+  }
+
+  let created_mod_folder = !mod_folder.exists();
+  debug!("Creating mod folder: {}", mod_folder.display());
+  if let Err(e) = fs::create_dir_all(&mod_folder) {
+    let error_msg = format!("Failed to create mod folder: {}", e);
+    emit_error(&error_msg);
+    return Err(error_msg);
+  }
+
+  let extraction_result = extract_archive(
+    &staged_path,
+    &mod_folder,
+    &name,
+    download_id,
+    &cancel_token,
+    &app
+  );
+  if let Err(e) = extraction_result {
+    if cancel_token.is_cancelled() {
+      return Err(
+        finish_cancelled_download(
+          &app,
+          download_id,
+          &name,
+          Some(&staged_path),
+          created_mod_folder.then_some(mod_folder.as_path())
+        )
+      );
+    }
+    if let Err(e) = fs::remove_file(&staged_path) {
+      warn!("Failed to delete staged copy of archive: {}", e);
+    }
+    // Don't leave a half extracted mod behind in a folder we made for it
+    if created_mod_folder {
+      fs::remove_dir_all(&mod_folder).unwrap_or_else(|e|
+        warn!("Failed to remove {}: {}", mod_folder.display(), e)
+      );
+    }
+    return Err(e);
+  }
+
+  if let Err(e) = reorganize_modpack(&mod_folder) {
+    warn!("Failed to reorganize modpack structure: {}", e);
+  }
+
+  let mut mod_info = match crate::filesystem::create_mod_info(&mod_folder.to_string_lossy()) {
+    Ok(mod_info) => mod_info,
+    Err(e) => {
+      emit_error(&e);
+      return Err(e);
+    }
+  };
+
+  // Keep the ID of the mod if it's still in the list
+  let mods_state = app.state::<crate::models::ModsState>();
+  {
+    let mut mods = mods_state.0.lock().unwrap();
+    if
+      let Some(existing_mod) = mods
+        .values()
+        .find(|existing_mod| existing_mod.path == mod_info.path)
+    {
+      mod_info.id = existing_mod.id.clone();
+    }
+    mods.insert(mod_info.id.clone(), mod_info.clone());
+  }
+  info!("Successfully reinstalled '{}' from archive {}", name, file_name);
+
+  app
+    .emit_download("download-finished", DownloadFinished {
+      mod_id: download_id,
+      name: name.clone(),
+      mod_info: mod_info.clone(),
+    })
+    .unwrap_or_else(|e|
+      error!("Failed to emit download-finished event: {}", e)
+    );
+
+  app
+    .emit_download("download-progress", DownloadProgress {
+      mod_id: download_id,
+      name: name.clone(),
+      bytes_downloaded: 100,
+      total_bytes: 100,
+      percentage: 100,
+      step_key: "app.notifications.download.installation_complete".to_string(),
+      step_variables: None,
+    })
+    .unwrap_or_else(|e|
+      error!("Failed to emit download-progress event: {}", e)
+    );
+
+  Ok(mod_info)
+}
+
 // Helper function to extract archives of different types
 fn extract_archive(
   download_path: &PathBuf,
   mod_folder: &PathBuf,
   name: &str,
   mod_id: i64,
+  cancel_token: &CancelToken,
   app: &tauri::AppHandle
 ) -> Result<(), String> {
   // Store the extraction result in a variable
   let result = if
     download_path.extension().and_then(|e| e.to_str()) == Some("zip")
   {
-    extract_zip_archive(download_path, mod_folder, name, mod_id, app)
+    extract_zip_archive(download_path, mod_folder, name, mod_id, cancel_token, app)
   } else if download_path.extension().and_then(|e| e.to_str()) == Some("7z") {
-    extract_7z_archive(download_path, mod_folder, name, mod_id, app)
+    extract_7z_archive(download_path, mod_folder, name, mod_id, cancel_token, app)
   } else if download_path.extension().and_then(|e| e.to_str()) == Some("rar") {
-    extract_rar_file(download_path, mod_folder, name, mod_id, app)
+    extract_rar_file(download_path, mod_folder, name, mod_id, cancel_token, app)
   } else {
     // Try to extract as zip by default
-    match extract_zip_archive(download_path, mod_folder, name, mod_id, app) {
+    match extract_zip_archive(download_path, mod_folder, name, mod_id, cancel_token, app) {
       Ok(_) => Ok(()),
+      Err(e) if cancel_token.is_cancelled() => Err(e),
       Err(e) => {
         let error_msg =
           format!("Could not extract archive with unknown format: {}", e);
@@ -1445,10 +1697,11 @@ fn extract_archive(
 
         // Emit error event
         app
-          .emit("download-error", DownloadError {
+          .emit_download("download-error", DownloadError {
             mod_id,
             name: name.to_string(),
             error: error_msg.clone(),
+            error_code: None,
           })
           .unwrap_or_else(|e|
             error!("Failed to emit download-error event: {}", e)
@@ -1459,18 +1712,11 @@ fn extract_archive(
     }
   };
 
-  // If extraction was successful, delete the archive file
+  // If extraction was successful, the staged archive isn't needed anymore
   if result.is_ok() {
-    debug!(
-      "Extraction successful, deleting archive file: {}",
-      download_path.display()
-    );
-    if let Err(e) = fs::remove_file(download_path) {
-      warn!("Failed to delete archive file after extraction: {}", e);
-      // Continue anyway as this is not critical
-    } else {
-      debug!("Archive file deleted successfully");
-    }
+    crate::filesystem::forget_executable_candidates(mod_folder);
+    debug!("Extraction successful, cleaning up {}", download_path.display());
+    crate::downloadcache::finish_installed_archive(download_path, name, mod_folder);
   }
 
   // Return the original extraction result
@@ -1600,6 +1846,7 @@ fn extract_zip_archive(
   mod_folder: &PathBuf,
   name: &str,
   mod_id: i64,
+  cancel_token: &CancelToken,
   app: &tauri::AppHandle
 ) -> Result<(), String> {
   let file = match std::fs::File::open(download_path) {
@@ -1610,10 +1857,11 @@ fn extract_zip_archive(
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id,
           name: name.to_string(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -1631,10 +1879,11 @@ fn extract_zip_archive(
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id,
           name: name.to_string(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -1648,6 +1897,9 @@ fn extract_zip_archive(
   let mut last_percentage = 80; // Starting percentage for extraction
 
   for i in 0..total_files {
+    if cancel_token.is_cancelled() {
+      return Err(DOWNLOAD_CANCELLED.to_string());
+    }
     let file_result = archive.by_index(i);
 
     let mut file = match file_result {
@@ -1674,7 +1926,7 @@ fn extract_zip_archive(
       variables.insert("total".to_string(), total_files.to_string());
 
       app
-        .emit("download-progress", DownloadProgress {
+        .emit_download("download-progress", DownloadProgress {
           mod_id,
           name: name.to_string(),
           bytes_downloaded: i,
@@ -1734,10 +1986,11 @@ fn extract_7z_archive(
   mod_folder: &PathBuf,
   name: &str,
   mod_id: i64,
+  cancel_token: &CancelToken,
   app: &tauri::AppHandle
 ) -> Result<(), String> {
   debug!("Extracting 7z archive: {}", download_path.display());
-  app.emit("download-progress", DownloadProgress {
+  app.emit_download("download-progress", DownloadProgress {
     mod_id,
     name: name.to_string(),
     bytes_downloaded: 80,
@@ -1747,13 +2000,23 @@ fn extract_7z_archive(
     step_variables: None,
   });
 
-  match sevenz_rust::decompress_file(download_path, mod_folder) {
+  let result = sevenz_rust::decompress_file_with_extract_fn(
+    download_path,
+    mod_folder,
+    |entry, reader, dest| {
+      if cancel_token.is_cancelled() {
+        return Err(sevenz_rust::Error::other(DOWNLOAD_CANCELLED));
+      }
+      sevenz_rust::default_entry_extract_fn(entry, reader, dest)
+    }
+  );
+  match result {
     Ok(_) => {
       debug!("Successfully extracted 7z archive to {}", mod_folder.display());
 
       // Since we don't have file count for progress, use a simple progress indicator
       app
-        .emit("download-progress", DownloadProgress {
+        .emit_download("download-progress", DownloadProgress {
           mod_id,
           name: name.to_string(),
           bytes_downloaded: 90,
@@ -1768,16 +2031,18 @@ fn extract_7z_archive(
 
       Ok(())
     }
+    Err(_) if cancel_token.is_cancelled() => Err(DOWNLOAD_CANCELLED.to_string()),
     Err(e) => {
       let error_msg = format!("Failed to extract 7z archive: {}", e);
       error!("{}", error_msg);
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id,
           name: name.to_string(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
@@ -1794,10 +2059,11 @@ fn extract_rar_file(
   mod_folder: &PathBuf,
   name: &str,
   mod_id: i64,
+  cancel_token: &CancelToken,
   app: &tauri::AppHandle
 ) -> Result<(), String> {
   debug!("Extracting RAR archive: {}", download_path.display());
-  app.emit("download-progress", DownloadProgress {
+  app.emit_download("download-progress", DownloadProgress {
     mod_id,
     name: name.to_string(),
     bytes_downloaded: 80,
@@ -1817,7 +2083,11 @@ fn extract_rar_file(
   // Open the archive for processing and extract all files
   match archive.open_for_processing() {
     Ok(open_archive) => {
-      let result = extract_rar_archive(open_archive, &extraction_path);
+      let result = extract_rar_archive(
+        open_archive,
+        &extraction_path,
+        cancel_token
+      );
 
       match result {
         Ok(_) => {
@@ -1828,7 +2098,7 @@ fn extract_rar_file(
 
           // Since we don't have file count for progress, use a simple progress indicator
           app
-            .emit("download-progress", DownloadProgress {
+            .emit_download("download-progress", DownloadProgress {
               mod_id,
               name: name.to_string(),
               bytes_downloaded: 90,
@@ -1843,16 +2113,18 @@ fn extract_rar_file(
 
           Ok(())
         }
+        Err(e) if cancel_token.is_cancelled() => Err(e),
         Err(e) => {
           let error_msg = format!("Failed to extract RAR archive: {}", e);
           error!("{}", error_msg);
 
           // Emit error event
           app
-            .emit("download-error", DownloadError {
+            .emit_download("download-error", DownloadError {
               mod_id,
               name: name.to_string(),
               error: error_msg.clone(),
+              error_code: None,
             })
             .unwrap_or_else(|e|
               error!("Failed to emit download-error event: {}", e)
@@ -1868,10 +2140,11 @@ fn extract_rar_file(
 
       // Emit error event
       app
-        .emit("download-error", DownloadError {
+        .emit_download("download-error", DownloadError {
           mod_id,
           name: name.to_string(),
           error: error_msg.clone(),
+          error_code: None,
         })
         .unwrap_or_else(|e|
           error!("Failed to emit download-error event: {}", e)
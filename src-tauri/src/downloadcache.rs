@@ -0,0 +1,430 @@
+use crate::models::ArchivedDownload;
+use lazy_static::lazy_static;
+use log::{ debug, info, warn };
+use serde::{ Deserialize, Serialize };
+use std::collections::{ HashMap, HashSet };
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::sync::Mutex;
+use tauri::Manager;
+
+// Folder in the app cache where downloads are staged until they're extracted
+const STAGING_FOLDER: &str = "downloads";
+// Folder in the app data directory holding archives kept after installing
+const ARCHIVE_LIBRARY_FOLDER: &str = "archives";
+// Prefix of the archives that finished downloading
+const ARCHIVE_PREFIX: &str = "FNF-";
+// Extension of the sidecar files next to partial downloads
+const SIDECAR_EXTENSION: &str = "json";
+
+pub const DEFAULT_MAX_CACHE_SIZE_MB: u64 = 4096;
+pub const DEFAULT_MAX_ARCHIVE_LIBRARY_SIZE_MB: u64 = 10240;
+
+lazy_static! {
+  // Staged files that a download is still using, these are never removed
+  static ref IN_USE: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
+  // Partial files of paused downloads by download ID, they're still in use
+  static ref PAUSED_PARTIALS: Mutex<HashMap<i64, InUseGuard>> = Mutex::new(
+    HashMap::new()
+  );
+}
+
+/// Keeps a staged file from being cleaned up until it's dropped
+pub struct InUseGuard {
+  path: PathBuf,
+}
+
+impl Drop for InUseGuard {
+  fn drop(&mut self) {
+    IN_USE.lock().unwrap().remove(&self.path);
+  }
+}
+
+/// Mark a staged file as used by a running download
+pub fn mark_in_use(path: &Path) -> InUseGuard {
+  IN_USE.lock().unwrap().insert(path.to_path_buf());
+  InUseGuard { path: path.to_path_buf() }
+}
+
+/// Mark the partial file of a download as in use, taking it back from the
+/// download's paused attempt if there was one. None if another download is
+/// already using it
+pub fn claim_partial(download_id: i64, path: &Path) -> Option<InUseGuard> {
+  let paused = PAUSED_PARTIALS.lock()
+    .unwrap()
+    .remove(&download_id)
+    .filter(|guard| guard.path == path);
+  if paused.is_some() {
+    return paused;
+  }
+  if !IN_USE.lock().unwrap().insert(path.to_path_buf()) {
+    return None;
+  }
+  Some(InUseGuard { path: path.to_path_buf() })
+}
+
+/// Keep the partial file of a paused download from being cleaned up until
+/// the download is resumed or cancelled
+pub fn keep_while_paused(download_id: i64, guard: InUseGuard) {
+  PAUSED_PARTIALS.lock().unwrap().insert(download_id, guard);
+}
+
+/// Get the folder downloads are staged in, creating it if needed
+pub fn get_staging_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  let dir = app
+    .path()
+    .app_cache_dir()
+    .map_err(|e| format!("Failed to get app cache directory: {}", e))?
+    .join(STAGING_FOLDER);
+  fs::create_dir_all(&dir).map_err(|e|
+    format!("Failed to create download cache {}: {}", dir.display(), e)
+  )?;
+  Ok(dir)
+}
+
+/// Get the folder archives are kept in after installing, creating it if needed
+pub fn get_archive_library_dir() -> Result<PathBuf, String> {
+  let dir = crate::utils::get_app_data_file(ARCHIVE_LIBRARY_FOLDER)?;
+  fs::create_dir_all(&dir).map_err(|e|
+    format!("Failed to create archive library {}: {}", dir.display(), e)
+  )?;
+  Ok(dir)
+}
+
+fn max_cache_size_bytes() -> u64 {
+  crate::downloadsettings
+    ::get_settings()
+    .max_cache_size_mb.unwrap_or(DEFAULT_MAX_CACHE_SIZE_MB)
+    .saturating_mul(1024 * 1024)
+}
+
+// A staged file, its size includes the sidecar that goes with it
+struct StagedEntry {
+  path: PathBuf,
+  size: u64,
+  modified: std::time::SystemTime,
+}
+
+fn sidecar_path(path: &Path) -> PathBuf {
+  let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+  file_name.push(".");
+  file_name.push(SIDECAR_EXTENSION);
+  path.with_file_name(file_name)
+}
+
+fn is_sidecar(path: &Path) -> bool {
+  path.extension().and_then(|e| e.to_str()) == Some(SIDECAR_EXTENSION)
+}
+
+fn find_staged_entries(staging_dir: &Path) -> Vec<StagedEntry> {
+  let entries = match fs::read_dir(staging_dir) {
+    Ok(entries) => entries,
+    Err(e) => {
+      warn!("Failed to read download cache {}: {}", staging_dir.display(), e);
+      return Vec::new();
+    }
+  };
+
+  entries
+    .filter_map(|e| e.ok())
+    .map(|e| e.path())
+    .filter(|path| path.is_file() && !is_sidecar(path))
+    .filter_map(|path| {
+      let metadata = fs::metadata(&path).ok()?;
+      let sidecar_size = fs
+        ::metadata(sidecar_path(&path))
+        .map(|m| m.len())
+        .unwrap_or(0);
+      Some(StagedEntry {
+        size: metadata.len() + sidecar_size,
+        modified: metadata.modified().unwrap_or(std::time::UNIX_EPOCH),
+        path,
+      })
+    })
+    .collect()
+}
+
+// Remove a staged file and its sidecar, unless a download is using it
+fn remove_entry(path: &Path) -> bool {
+  let in_use = IN_USE.lock().unwrap();
+  if in_use.contains(path) {
+    return false;
+  }
+  for file in [path.to_path_buf(), sidecar_path(path)] {
+    match fs::remove_file(&file) {
+      Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+        warn!("Failed to delete {}: {}", file.display(), e);
+        return false;
+      }
+      _ => {}
+    }
+  }
+  debug!("Removed {} from the download cache", path.display());
+  true
+}
+
+/// Make room in the download cache for a download of the given size,
+/// removing the oldest staged files first. Returns the number of bytes freed
+pub fn enforce_size_cap(app: &tauri::AppHandle, incoming_bytes: u64) -> u64 {
+  let staging_dir = match get_staging_dir(app) {
+    Ok(dir) => dir,
+    Err(e) => {
+      warn!("{}", e);
+      return 0;
+    }
+  };
+  let max_size = max_cache_size_bytes();
+
+  let mut entries = find_staged_entries(&staging_dir);
+  // Oldest first
+  entries.sort_by_key(|entry| entry.modified);
+  let mut total_size =
+    entries
+      .iter()
+      .map(|entry| entry.size)
+      .sum::<u64>() + incoming_bytes;
+
+  let mut freed_bytes = 0;
+  for entry in entries {
+    if total_size <= max_size {
+      break;
+    }
+    if remove_entry(&entry.path) {
+      total_size -= entry.size;
+      freed_bytes += entry.size;
+    }
+  }
+
+  if total_size > max_size {
+    warn!(
+      "Download cache is still {} bytes over its {} byte limit",
+      total_size - max_size,
+      max_size
+    );
+  }
+  if freed_bytes > 0 {
+    info!("Freed {} bytes from the download cache", freed_bytes);
+  }
+  freed_bytes
+}
+
+/// Make room for the rest of a download once its total size is known
+pub fn make_room_for_download(
+  app: &tauri::AppHandle,
+  partial_path: &Path,
+  total_bytes: usize
+) {
+  // Whatever was resumed is already in the cache
+  let existing_bytes = fs
+    ::metadata(partial_path)
+    .map(|m| m.len())
+    .unwrap_or(0);
+  enforce_size_cap(app, (total_bytes as u64).saturating_sub(existing_bytes));
+}
+
+/// Remove archives left behind by installs that never finished, and
+/// sidecars without a partial download. Partial downloads are kept so they
+/// can be resumed, as long as the cache stays under its size limit
+pub fn clean_up_on_startup(app: &tauri::AppHandle) {
+  let staging_dir = match get_staging_dir(app) {
+    Ok(dir) => dir,
+    Err(e) => {
+      warn!("{}", e);
+      return;
+    }
+  };
+
+  let mut removed = 0;
+  for entry in find_staged_entries(&staging_dir) {
+    let is_archive = entry.path
+      .file_name()
+      .and_then(|n| n.to_str())
+      .is_some_and(|n| n.starts_with(ARCHIVE_PREFIX));
+    if is_archive && remove_entry(&entry.path) {
+      removed += 1;
+    }
+  }
+
+  // Sidecars whose partial download is gone
+  if let Ok(entries) = fs::read_dir(&staging_dir) {
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+      if !is_sidecar(&path) || path.with_extension("").exists() {
+        continue;
+      }
+      if let Err(e) = fs::remove_file(&path) {
+        warn!("Failed to delete {}: {}", path.display(), e);
+      }
+    }
+  }
+
+  if removed > 0 {
+    info!("Removed {} orphaned archives from the download cache", removed);
+  }
+  enforce_size_cap(app, 0);
+}
+
+// What an archive in the library was installed as, kept in its sidecar
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ArchiveInstallInfo {
+  name: String,
+  install_path: String,
+}
+
+/// Get rid of an archive once it's been installed, moving it to the archive
+/// library instead if the settings say to keep it
+pub fn finish_installed_archive(archive_path: &Path, name: &str, install_path: &Path) {
+  if crate::downloadsettings::get_settings().keep_archives_after_install {
+    match move_to_library(archive_path) {
+      Ok(path) => {
+        info!("Kept installed archive at {}", path.display());
+        let install_info = ArchiveInstallInfo {
+          name: name.to_string(),
+          install_path: install_path.to_string_lossy().to_string(),
+        };
+        if let Err(e) = crate::utils::write_json_file(&sidecar_path(&path), &install_info) {
+          warn!("Failed to save install info of kept archive: {}", e);
+        }
+        enforce_library_size_cap(&path);
+        return;
+      }
+      Err(e) => warn!("Failed to keep installed archive: {}", e),
+    }
+  }
+
+  debug!("Deleting archive file: {}", archive_path.display());
+  if let Err(e) = fs::remove_file(archive_path) {
+    warn!("Failed to delete archive file after extraction: {}", e);
+  }
+}
+
+fn move_to_library(archive_path: &Path) -> Result<PathBuf, String> {
+  let file_name = archive_path
+    .file_name()
+    .ok_or_else(|| format!("Invalid archive path: {}", archive_path.display()))?;
+  let target = get_archive_library_dir()?.join(file_name);
+  move_file(archive_path, &target)?;
+  Ok(target)
+}
+
+/// Move a file out of the download cache, copying it if it can't be renamed
+/// because the cache and the target are on different drives
+pub fn move_file(path: &Path, target: &Path) -> Result<(), String> {
+  if fs::rename(path, target).is_err() {
+    fs::copy(path, target).map_err(|e|
+      format!("Failed to copy {} to {}: {}", path.display(), target.display(), e)
+    )?;
+    if let Err(e) = fs::remove_file(path) {
+      warn!("Failed to delete {} after copying it: {}", path.display(), e);
+    }
+  }
+  Ok(())
+}
+
+fn max_library_size_bytes() -> u64 {
+  crate::downloadsettings
+    ::get_settings()
+    .max_archive_library_size_mb.unwrap_or(DEFAULT_MAX_ARCHIVE_LIBRARY_SIZE_MB)
+    .saturating_mul(1024 * 1024)
+}
+
+// Remove the oldest archives until the library is under its size limit,
+// the archive that was just kept always stays
+fn enforce_library_size_cap(kept_path: &Path) {
+  let dir = match get_archive_library_dir() {
+    Ok(dir) => dir,
+    Err(e) => {
+      warn!("{}", e);
+      return;
+    }
+  };
+  let max_size = max_library_size_bytes();
+
+  let mut entries = find_staged_entries(&dir);
+  // Oldest first
+  entries.sort_by_key(|entry| entry.modified);
+  let mut total_size = entries
+    .iter()
+    .map(|entry| entry.size)
+    .sum::<u64>();
+
+  for entry in entries {
+    if total_size <= max_size {
+      break;
+    }
+    if entry.path == kept_path {
+      continue;
+    }
+    if remove_entry(&entry.path) {
+      info!("Removed {} to keep the archive library under its size limit", entry.path.display());
+      total_size -= entry.size;
+    }
+  }
+}
+
+/// List the archives in the archive library, newest first
+pub fn list_archives() -> Result<Vec<ArchivedDownload>, String> {
+  let dir = get_archive_library_dir()?;
+  let entries = fs
+    ::read_dir(&dir)
+    .map_err(|e| format!("Failed to read archive library: {}", e))?;
+
+  let mut archives = entries
+    .filter_map(|e| e.ok())
+    .filter(|entry| !is_sidecar(&entry.path()))
+    .filter_map(|entry| {
+      let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+      let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+      let install_info: Option<ArchiveInstallInfo> = crate::utils::read_json_file(
+        &sidecar_path(&entry.path())
+      );
+      Some(ArchivedDownload {
+        file_name: entry.file_name().to_string_lossy().to_string(),
+        path: entry.path().to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified,
+        name: install_info.as_ref().map(|i| i.name.clone()),
+        install_path: install_info.map(|i| i.install_path),
+      })
+    })
+    .collect::<Vec<_>>();
+  archives.sort_by_key(|a| std::cmp::Reverse(a.modified));
+  Ok(archives)
+}
+
+// Only plain file names, so nothing outside the library can be touched
+fn library_archive_path(file_name: &str) -> Result<PathBuf, String> {
+  if Path::new(file_name).file_name().and_then(|n| n.to_str()) != Some(file_name) {
+    return Err(format!("Invalid archive name: {}", file_name));
+  }
+  Ok(get_archive_library_dir()?.join(file_name))
+}
+
+/// Find an archive in the archive library
+pub fn get_archive(file_name: &str) -> Result<ArchivedDownload, String> {
+  let path = library_archive_path(file_name)?;
+  list_archives()?
+    .into_iter()
+    .find(|archive| Path::new(&archive.path) == path)
+    .ok_or_else(|| format!("Archive {} is not in the archive library", file_name))
+}
+
+/// Delete an archive from the archive library
+pub fn delete_archive(file_name: &str) -> Result<(), String> {
+  let path = library_archive_path(file_name)?;
+  fs::remove_file(&path).map_err(|e|
+    format!("Failed to delete archive {}: {}", path.display(), e)
+  )?;
+  match fs::remove_file(sidecar_path(&path)) {
+    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+      warn!("Failed to delete install info of archive {}: {}", file_name, e);
+    }
+    _ => {}
+  }
+  info!("Deleted archive {}", path.display());
+  Ok(())
+}
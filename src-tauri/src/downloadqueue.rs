@@ -0,0 +1,510 @@
+use crate::models::{
+  DownloadCancelled,
+  DownloadError,
+  DownloadFinished,
+  DownloadJob,
+  DownloadJobState,
+  DownloadProgress,
+  DownloadStarted,
+};
+use futures_util::stream::{ AbortHandle, Abortable, Stream };
+use lazy_static::lazy_static;
+use log::{ debug, info, warn };
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{ AtomicBool, Ordering };
+use std::sync::{ Arc, Mutex };
+use tauri::Emitter;
+use tokio::sync::Notify;
+
+// Error returned by downloads that were cancelled
+pub const DOWNLOAD_CANCELLED: &str = "Download cancelled";
+// Error returned by downloads that were paused, they run again when resumed
+pub const DOWNLOAD_PAUSED: &str = "Download paused";
+
+// How many downloads run at once when the settings don't say
+pub const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 2;
+// Finished jobs kept around for list_downloads
+const MAX_FINISHED_JOBS: usize = 50;
+
+lazy_static! {
+  // Every job in queue order, the order only matters for the ones waiting
+  static ref DOWNLOAD_QUEUE: Mutex<Vec<QueuedJob>> = Mutex::new(Vec::new());
+  // Wakes up the jobs waiting for their turn whenever the queue changes
+  static ref QUEUE_CHANGED: Notify = Notify::new();
+}
+
+struct QueuedJob {
+  job: DownloadJob,
+  token: CancelToken,
+}
+
+/// Lets a running download know it was cancelled or paused
+#[derive(Clone, Default)]
+pub struct CancelToken {
+  cancelled: Arc<AtomicBool>,
+  paused: Arc<AtomicBool>,
+  // Stops the byte stream even while it's waiting for data
+  abort_handle: Arc<Mutex<Option<AbortHandle>>>,
+}
+
+impl CancelToken {
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused.load(Ordering::SeqCst)
+  }
+
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::SeqCst);
+    self.abort_stream();
+  }
+
+  /// Stop downloading but keep what was downloaded, extraction isn't paused
+  pub fn pause(&self) {
+    self.paused.store(true, Ordering::SeqCst);
+    self.abort_stream();
+  }
+
+  fn abort_stream(&self) {
+    if let Some(abort_handle) = self.abort_handle.lock().unwrap().take() {
+      abort_handle.abort();
+    }
+  }
+
+  /// Wrap a stream so it ends as soon as the download is cancelled or paused
+  pub fn abortable_stream<St: Stream>(&self, stream: St) -> Abortable<St> {
+    let (stream, abort_handle) = futures_util::stream::abortable(stream);
+    if self.is_cancelled() || self.is_paused() {
+      abort_handle.abort();
+    }
+    *self.abort_handle.lock().unwrap() = Some(abort_handle);
+    stream
+  }
+}
+
+// What a job waiting for its turn should do
+enum Turn {
+  Start(CancelToken),
+  Wait,
+  Cancelled,
+}
+
+/// Queue a download and run it once it's its turn. A job that's paused while
+/// running is started again when it's resumed, so start has to be able to
+/// pick up where it left off
+pub async fn run<T, F, Fut>(
+  app: &tauri::AppHandle,
+  id: i64,
+  name: String,
+  mut start: F
+) -> Result<T, String>
+  where F: FnMut(CancelToken) -> Fut, Fut: Future<Output = Result<T, String>>
+{
+  enqueue(id, &name)?;
+  let mut paused_token: Option<CancelToken> = None;
+
+  loop {
+    let token = match (wait_for_turn(id).await, paused_token.take()) {
+      (Some(token), _) => token,
+      // A job that was paused while running has files to clean up,
+      // running it with the cancelled token takes care of that
+      (None, Some(token)) => {
+        info!("Download {} was cancelled while paused", name);
+        let result = start(token).await;
+        update_job(id, |job| {
+          job.state = DownloadJobState::Cancelled;
+        });
+        return result;
+      }
+      (None, None) => {
+        info!("Download {} was cancelled before it started", name);
+        app
+          .emit_download("download-cancelled", DownloadCancelled {
+            mod_id: id,
+            name: name.clone(),
+          })
+          .unwrap_or_else(|e|
+            warn!("Failed to emit download-cancelled event: {}", e)
+          );
+        return Err(DOWNLOAD_CANCELLED.to_string());
+      }
+    };
+
+    let result = start(token.clone()).await;
+
+    let state = match &result {
+      Ok(_) => DownloadJobState::Completed,
+      Err(_) if token.is_cancelled() => DownloadJobState::Cancelled,
+      Err(e) if e == DOWNLOAD_PAUSED => DownloadJobState::Paused,
+      Err(_) => DownloadJobState::Failed,
+    };
+    update_job(id, |job| {
+      job.state = state;
+      if let (Err(e), DownloadJobState::Failed) = (&result, state) {
+        job.error = Some(e.clone());
+      }
+    });
+
+    if state == DownloadJobState::Paused {
+      info!("Download {} was paused", name);
+      paused_token = Some(token);
+      continue;
+    }
+    return result;
+  }
+}
+
+/// Get every job in the queue, including recently finished ones
+pub fn list_jobs() -> Vec<DownloadJob> {
+  let queue = DOWNLOAD_QUEUE.lock().unwrap();
+  with_positions(&queue)
+}
+
+/// Cancel a download, whether it's running or still waiting.
+/// Returns false if there's no unfinished download with that ID
+pub fn cancel(id: i64) -> bool {
+  let mut queue = DOWNLOAD_QUEUE.lock().unwrap();
+  let queued_job = match queue.iter_mut().find(|j| j.job.id == id) {
+    Some(queued_job) => queued_job,
+    None => {
+      return false;
+    }
+  };
+
+  match queued_job.job.state {
+    DownloadJobState::Running => {
+      info!("Cancelling download {}", id);
+      queued_job.token.cancel();
+      true
+    }
+    DownloadJobState::Queued | DownloadJobState::Paused => {
+      info!("Cancelling queued download {}", id);
+      queued_job.token.cancel();
+      queued_job.job.state = DownloadJobState::Cancelled;
+      drop(queue);
+      queue_changed();
+      true
+    }
+    _ => false,
+  }
+}
+
+/// Pause a download, a running one stops downloading but keeps what it has
+pub fn pause(id: i64) -> Result<(), String> {
+  let mut queue = DOWNLOAD_QUEUE.lock().unwrap();
+  let queued_job = find_job(&mut queue, id)?;
+  match queued_job.job.state {
+    DownloadJobState::Queued => {
+      queued_job.job.state = DownloadJobState::Paused;
+    }
+    // The job becomes paused once it actually stops
+    DownloadJobState::Running => {
+      queued_job.token.pause();
+    }
+    DownloadJobState::Paused => {
+      return Ok(());
+    }
+    state => {
+      return Err(format!("Can't pause a download that's {:?}", state));
+    }
+  }
+  info!("Pausing download {}", id);
+  drop(queue);
+  queue_changed();
+  Ok(())
+}
+
+/// Put a paused download back in the queue
+pub fn resume(id: i64) -> Result<(), String> {
+  let mut queue = DOWNLOAD_QUEUE.lock().unwrap();
+  let queued_job = find_job(&mut queue, id)?;
+  if queued_job.job.state != DownloadJobState::Paused {
+    return Err(format!("Download {} isn't paused", id));
+  }
+  queued_job.job.state = DownloadJobState::Queued;
+  info!("Resuming download {}", id);
+  drop(queue);
+  queue_changed();
+  Ok(())
+}
+
+/// Move a queued or paused download to another place in the queue, 0 is next
+pub fn move_job(id: i64, position: usize) -> Result<(), String> {
+  let mut queue = DOWNLOAD_QUEUE.lock().unwrap();
+  let index = queue
+    .iter()
+    .position(|j| j.job.id == id && is_waiting(j.job.state))
+    .ok_or_else(|| format!("Download {} isn't waiting in the queue", id))?;
+  let queued_job = queue.remove(index);
+
+  let waiting_indexes = queue
+    .iter()
+    .enumerate()
+    .filter(|(_, j)| is_waiting(j.job.state))
+    .map(|(i, _)| i)
+    .collect::<Vec<_>>();
+  let target = match waiting_indexes.get(position) {
+    Some(target) => *target,
+    None => waiting_indexes.last().map_or(queue.len(), |last| last + 1),
+  };
+  queue.insert(target, queued_job);
+
+  debug!("Moved download {} to position {}", id, position);
+  drop(queue);
+  queue_changed();
+  Ok(())
+}
+
+/// Let waiting jobs check again, for when the concurrency limit changed
+pub fn queue_changed() {
+  QUEUE_CHANGED.notify_waiters();
+  if let Err(e) = crate::app_handle::emit_event("download-queue-changed", list_jobs()) {
+    warn!("Failed to emit download-queue-changed event: {}", e);
+  }
+}
+
+fn max_concurrent_downloads() -> usize {
+  crate::downloadsettings
+    ::get_settings()
+    .max_concurrent_downloads
+    .unwrap_or(DEFAULT_MAX_CONCURRENT_DOWNLOADS)
+    .max(1)
+}
+
+fn enqueue(id: i64, name: &str) -> Result<(), String> {
+  let mut queue = DOWNLOAD_QUEUE.lock().unwrap();
+  if queue.iter().any(|j| j.job.id == id && !is_finished(j.job.state)) {
+    return Err(format!("Download {} is already in the queue", id));
+  }
+  queue.retain(|j| j.job.id != id);
+
+  // Forget the oldest finished jobs
+  let finished_count = queue
+    .iter()
+    .filter(|j| is_finished(j.job.state))
+    .count();
+  let mut to_forget = finished_count.saturating_sub(MAX_FINISHED_JOBS - 1);
+  queue.retain(|j| {
+    if to_forget > 0 && is_finished(j.job.state) {
+      to_forget -= 1;
+      return false;
+    }
+    true
+  });
+
+  queue.push(QueuedJob {
+    job: DownloadJob {
+      id,
+      name: name.to_string(),
+      state: DownloadJobState::Queued,
+      queue_position: None,
+      percentage: 0,
+      error: None,
+      queued_at: chrono::Utc::now().timestamp(),
+    },
+    token: CancelToken::default(),
+  });
+  info!("Queued download {} ({})", name, id);
+  drop(queue);
+  queue_changed();
+  Ok(())
+}
+
+// Wait until the job can run, returns None if it was cancelled while waiting
+async fn wait_for_turn(id: i64) -> Option<CancelToken> {
+  loop {
+    // Listen before checking so a change in between isn't missed
+    let notified = QUEUE_CHANGED.notified();
+    let mut notified = std::pin::pin!(notified);
+    notified.as_mut().enable();
+
+    match try_start(id) {
+      Turn::Start(token) => {
+        queue_changed();
+        return Some(token);
+      }
+      Turn::Cancelled => {
+        return None;
+      }
+      Turn::Wait => {}
+    }
+    notified.await;
+  }
+}
+
+fn try_start(id: i64) -> Turn {
+  let mut queue = DOWNLOAD_QUEUE.lock().unwrap();
+  let running_count = queue
+    .iter()
+    .filter(|j| j.job.state == DownloadJobState::Running)
+    .count();
+  // Only the first queued job gets to start, paused ones keep their place
+  let next_id = queue
+    .iter()
+    .find(|j| j.job.state == DownloadJobState::Queued)
+    .map(|j| j.job.id);
+
+  let queued_job = match queue.iter_mut().find(|j| j.job.id == id) {
+    Some(queued_job) => queued_job,
+    None => {
+      return Turn::Cancelled;
+    }
+  };
+  if queued_job.token.is_cancelled() {
+    return Turn::Cancelled;
+  }
+  if next_id != Some(id) || running_count >= max_concurrent_downloads() {
+    return Turn::Wait;
+  }
+
+  // A job that was paused while running starts over with a fresh pause flag
+  queued_job.token.paused.store(false, Ordering::SeqCst);
+  queued_job.job.state = DownloadJobState::Running;
+  queued_job.job.error = None;
+  debug!("Starting download {}", id);
+  Turn::Start(queued_job.token.clone())
+}
+
+fn update_job<F: FnOnce(&mut DownloadJob)>(id: i64, update: F) {
+  {
+    let mut queue = DOWNLOAD_QUEUE.lock().unwrap();
+    if let Some(queued_job) = queue.iter_mut().find(|j| j.job.id == id) {
+      update(&mut queued_job.job);
+    }
+  }
+  queue_changed();
+}
+
+fn find_job(queue: &mut [QueuedJob], id: i64) -> Result<&mut QueuedJob, String> {
+  queue
+    .iter_mut()
+    .find(|j| j.job.id == id)
+    .ok_or_else(|| format!("No download with ID {}", id))
+}
+
+fn is_waiting(state: DownloadJobState) -> bool {
+  matches!(state, DownloadJobState::Queued | DownloadJobState::Paused)
+}
+
+fn is_finished(state: DownloadJobState) -> bool {
+  matches!(
+    state,
+    DownloadJobState::Completed |
+      DownloadJobState::Failed |
+      DownloadJobState::Cancelled
+  )
+}
+
+// The jobs with their place among the ones waiting
+fn with_positions(queue: &[QueuedJob]) -> Vec<DownloadJob> {
+  let mut position = 0;
+  queue
+    .iter()
+    .map(|queued_job| {
+      let mut job = queued_job.job.clone();
+      if is_waiting(job.state) {
+        job.queue_position = Some(position);
+        position += 1;
+      }
+      job
+    })
+    .collect()
+}
+
+fn get_job(id: i64) -> Option<DownloadJob> {
+  let queue = DOWNLOAD_QUEUE.lock().unwrap();
+  with_positions(&queue)
+    .into_iter()
+    .find(|j| j.id == id)
+}
+
+/// Payloads of the download events
+pub trait DownloadEvent: Serialize + Clone {
+  fn download_id(&self) -> i64;
+  // The state the job is in once the event is sent
+  fn job_state(&self) -> Option<DownloadJobState> {
+    None
+  }
+  fn percentage(&self) -> Option<u8> {
+    None
+  }
+}
+
+impl DownloadEvent for DownloadStarted {
+  fn download_id(&self) -> i64 {
+    self.mod_id
+  }
+}
+
+impl DownloadEvent for DownloadProgress {
+  fn download_id(&self) -> i64 {
+    self.mod_id
+  }
+  fn percentage(&self) -> Option<u8> {
+    Some(self.percentage)
+  }
+}
+
+impl DownloadEvent for DownloadFinished {
+  fn download_id(&self) -> i64 {
+    self.mod_id
+  }
+  fn job_state(&self) -> Option<DownloadJobState> {
+    Some(DownloadJobState::Completed)
+  }
+}
+
+impl DownloadEvent for DownloadError {
+  fn download_id(&self) -> i64 {
+    self.mod_id
+  }
+  fn job_state(&self) -> Option<DownloadJobState> {
+    Some(DownloadJobState::Failed)
+  }
+}
+
+impl DownloadEvent for DownloadCancelled {
+  fn download_id(&self) -> i64 {
+    self.mod_id
+  }
+  fn job_state(&self) -> Option<DownloadJobState> {
+    Some(DownloadJobState::Cancelled)
+  }
+}
+
+// A download event along with where the download is in the queue
+#[derive(Serialize, Clone)]
+struct QueuedDownloadEvent<T: Serialize + Clone> {
+  #[serde(flatten)]
+  payload: T,
+  queue_position: Option<usize>,
+  job_state: Option<DownloadJobState>,
+}
+
+/// Emit download events with the queue position and state of their job
+pub trait EmitDownloadEvent {
+  fn emit_download<T: DownloadEvent>(&self, event: &str, payload: T) -> tauri::Result<()>;
+}
+
+impl EmitDownloadEvent for tauri::AppHandle {
+  fn emit_download<T: DownloadEvent>(&self, event: &str, payload: T) -> tauri::Result<()> {
+    let id = payload.download_id();
+    if let Some(percentage) = payload.percentage() {
+      let mut queue = DOWNLOAD_QUEUE.lock().unwrap();
+      if let Some(queued_job) = queue.iter_mut().find(|j| j.job.id == id) {
+        queued_job.job.percentage = percentage;
+      }
+    }
+
+    let job = get_job(id);
+    let job_state = payload.job_state().or(job.as_ref().map(|j| j.state));
+    self.emit(event, QueuedDownloadEvent {
+      queue_position: job.and_then(|j| j.queue_position),
+      job_state,
+      payload,
+    })
+  }
+}
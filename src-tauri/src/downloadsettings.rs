@@ -0,0 +1,41 @@
+use crate::models::DownloadSettings;
+use lazy_static::lazy_static;
+use log::{ error, info };
+use std::sync::Mutex;
+
+// File in the app data directory holding the download settings
+const DOWNLOAD_SETTINGS_FILE: &str = "download_settings.json";
+
+lazy_static! {
+  // The settings, loaded from disk the first time they're needed. Also
+  // serializes writes of the settings file
+  static ref CACHED_SETTINGS: Mutex<Option<DownloadSettings>> = Mutex::new(None);
+}
+
+/// Get the download settings
+pub fn get_settings() -> DownloadSettings {
+  let mut cached = CACHED_SETTINGS.lock().unwrap();
+  if let Some(settings) = cached.as_ref() {
+    return settings.clone();
+  }
+
+  let settings = match crate::utils::get_app_data_file(DOWNLOAD_SETTINGS_FILE) {
+    Ok(path) => crate::utils::read_json_file(&path),
+    Err(e) => {
+      error!("Failed to resolve download settings file: {}", e);
+      DownloadSettings::default()
+    }
+  };
+  *cached = Some(settings.clone());
+  settings
+}
+
+/// Save the download settings
+pub fn set_settings(settings: &DownloadSettings) -> Result<(), String> {
+  let mut cached = CACHED_SETTINGS.lock().unwrap();
+  let path = crate::utils::get_app_data_file(DOWNLOAD_SETTINGS_FILE)?;
+  crate::utils::write_json_file(&path, settings)?;
+  *cached = Some(settings.clone());
+  info!("Saved download settings: {:?}", settings);
+  Ok(())
+}
@@ -1,10 +1,21 @@
-use crate::models::{ ModInfo, MIN_METADATA_VERSION };
+use crate::models::{
+  ExecutableCandidate,
+  ExecutableKind,
+  LaunchHooks,
+  LaunchProfile,
+  ModInfo,
+  MIN_METADATA_VERSION,
+};
 use base64::{ engine::general_purpose::STANDARD as BASE64, Engine as _ };
 use image::{ ImageBuffer, Rgba };
+use lazy_static::lazy_static;
 use log::{ debug, error, warn, info };
+use std::collections::HashMap;
 use std::fs;
 use std::path::{ Path, PathBuf };
 use std::ptr;
+use std::sync::Mutex;
+use std::time::SystemTime;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::ffi::OsStrExt;
@@ -13,14 +24,32 @@ use winapi::shared::minwindef::HINSTANCE;
 #[cfg(target_os = "windows")]
 use winapi::um::shellapi::ExtractIconW;
 
-// Find the executable file(s) within a directory
+type CachedCandidates = (Option<SystemTime>, Vec<ExecutableCandidate>);
+
+lazy_static! {
+  // Executable candidates of each folder scanned so far and the folder's
+  // modified time at that point, so mods loaded from the database don't get
+  // their whole folder scanned again on every launch
+  static ref CANDIDATE_CACHE: Mutex<HashMap<PathBuf, CachedCandidates>> =
+    Mutex::new(HashMap::new());
+}
+
+// Find the executable file(s) within a directory, best match for this platform first
 pub fn find_executables(dir_path: &Path) -> Vec<PathBuf> {
-  let mut executables = Vec::new();
+  find_executable_candidates(dir_path)
+    .into_iter()
+    .map(|candidate| PathBuf::from(candidate.path))
+    .collect()
+}
+
+// Find all launchable files within a directory and rank them for the current platform
+pub fn find_executable_candidates(dir_path: &Path) -> Vec<ExecutableCandidate> {
+  let mut executables: Vec<(ExecutableCandidate, usize)> = Vec::new();
 
   // Track recursion depth to prevent going too deep
   fn search_directory(
     dir_path: &Path,
-    executables: &mut Vec<PathBuf>,
+    executables: &mut Vec<(ExecutableCandidate, usize)>,
     depth: usize
   ) {
     // Limit recursion depth so we dont blow up everything
@@ -35,16 +64,19 @@ pub fn find_executables(dir_path: &Path) -> Vec<PathBuf> {
         let path = entry.path();
 
         if path.is_file() {
-          // On Windows, look for .exe files
-          if let Some(extension) = path.extension() {
-            if extension.to_string_lossy().to_lowercase() == "exe" {
-              let file_name = path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy();
-              debug!("Found executable: {}", file_name);
-              executables.push(path.clone());
-            }
+          if let Some(kind) = detect_executable_kind(&path) {
+            let file_name = path
+              .file_name()
+              .unwrap_or_default()
+              .to_string_lossy();
+            debug!("Found executable ({:?}): {}", kind, file_name);
+            executables.push((
+              ExecutableCandidate {
+                path: path.to_string_lossy().to_string(),
+                kind,
+              },
+              depth,
+            ));
           }
         } else if path.is_dir() {
           // Recursively search subdirectories
@@ -57,6 +89,20 @@ pub fn find_executables(dir_path: &Path) -> Vec<PathBuf> {
   // Start the recursive search at depth 0
   search_directory(dir_path, &mut executables, 0);
 
+  // Rank candidates: native builds first, then shallower files, then the
+  // ones that don't look like helper tools (crash handlers, uninstallers...)
+  executables.sort_by_key(|(candidate, depth)| {
+    (
+      platform_rank(candidate.kind),
+      *depth,
+      is_helper_executable(Path::new(&candidate.path)),
+    )
+  });
+  let executables: Vec<ExecutableCandidate> = executables
+    .into_iter()
+    .map(|(candidate, _)| candidate)
+    .collect();
+
   if executables.is_empty() {
     debug!("No executables found in: {}", dir_path.display());
   } else {
@@ -65,14 +111,211 @@ pub fn find_executables(dir_path: &Path) -> Vec<PathBuf> {
       dir_path.display(),
       executables
         .iter()
-        .map(|p| p.file_name().unwrap_or_default().to_string_lossy())
+        .map(|c| {
+          Path::new(&c.path)
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+        })
         .collect::<Vec<_>>()
     );
   }
 
+  CANDIDATE_CACHE.lock()
+    .unwrap()
+    .insert(
+      dir_path.to_path_buf(),
+      (folder_modified_time(dir_path), executables.clone())
+    );
   executables
 }
 
+fn folder_modified_time(dir_path: &Path) -> Option<SystemTime> {
+  fs::metadata(dir_path)
+    .and_then(|m| m.modified())
+    .ok()
+}
+
+// Executable candidates of a directory, scanning it again if it changed since
+// the last scan or the last scan found nothing
+pub fn cached_executable_candidates(dir_path: &Path) -> Vec<ExecutableCandidate> {
+  if
+    let Some((_, candidates)) = CANDIDATE_CACHE.lock()
+      .unwrap()
+      .get(dir_path)
+      .filter(|(modified, candidates)| {
+        !candidates.is_empty() &&
+          modified.is_some() &&
+          *modified == folder_modified_time(dir_path)
+      })
+  {
+    return candidates.clone();
+  }
+  find_executable_candidates(dir_path)
+}
+
+// Drop the cached candidates of a directory, for when its contents changed
+pub fn forget_executable_candidates(dir_path: &Path) {
+  CANDIDATE_CACHE.lock().unwrap().remove(dir_path);
+}
+
+// Figure out whether a file is something we know how to launch
+pub fn detect_executable_kind(path: &Path) -> Option<ExecutableKind> {
+  let extension = path
+    .extension()
+    .map(|e| e.to_string_lossy().to_lowercase())
+    .unwrap_or_default();
+
+  if extension == "exe" {
+    return Some(ExecutableKind::WindowsExe);
+  }
+
+  // Everything else needs the execute bit, which only exists on Unix
+  if !has_execute_bit(path) {
+    return None;
+  }
+
+  match extension.as_str() {
+    "appimage" => Some(ExecutableKind::AppImage),
+    "sh" => Some(ExecutableKind::ShellScript),
+    // Lime/Haxe builds ship their native libraries as ELF files too, skip them
+    "so" | "ndll" | "dll" | "a" | "o" | "dylib" => None,
+    _ => {
+      let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+      if file_name.contains(".so.") {
+        return None;
+      }
+      if is_elf_binary(path) { Some(ExecutableKind::LinuxElf) } else { None }
+    }
+  }
+}
+
+// Check the ELF magic bytes at the start of a file
+fn is_elf_binary(path: &Path) -> bool {
+  use std::io::Read;
+
+  let mut magic = [0u8; 4];
+  match fs::File::open(path) {
+    Ok(mut file) => file.read_exact(&mut magic).is_ok() && magic == *b"\x7fELF",
+    Err(_) => false,
+  }
+}
+
+#[cfg(unix)]
+fn has_execute_bit(path: &Path) -> bool {
+  use std::os::unix::fs::PermissionsExt;
+
+  fs::metadata(path)
+    .map(|m| m.permissions().mode() & 0o111 != 0)
+    .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn has_execute_bit(_path: &Path) -> bool {
+  // No execute bit outside of Unix, so only .exe files count as executables
+  false
+}
+
+// Lower is better. Native builds come first, then whatever else could still work
+fn platform_rank(kind: ExecutableKind) -> u8 {
+  #[cfg(target_os = "windows")]
+  {
+    match kind {
+      ExecutableKind::WindowsExe => 0,
+      ExecutableKind::ShellScript => 1,
+      ExecutableKind::AppImage => 2,
+      ExecutableKind::LinuxElf => 3,
+    }
+  }
+  #[cfg(target_os = "linux")]
+  {
+    match kind {
+      ExecutableKind::LinuxElf => 0,
+      ExecutableKind::AppImage => 1,
+      ExecutableKind::ShellScript => 2,
+      ExecutableKind::WindowsExe => 3,
+    }
+  }
+  #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+  {
+    match kind {
+      ExecutableKind::ShellScript => 0,
+      ExecutableKind::WindowsExe => 1,
+      ExecutableKind::LinuxElf => 2,
+      ExecutableKind::AppImage => 3,
+    }
+  }
+}
+
+// Whether an executable of this kind can be started directly on this platform
+pub fn is_native_executable(kind: ExecutableKind) -> bool {
+  #[cfg(target_os = "windows")]
+  {
+    kind == ExecutableKind::WindowsExe
+  }
+  #[cfg(target_os = "linux")]
+  {
+    kind != ExecutableKind::WindowsExe
+  }
+  #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+  {
+    kind == ExecutableKind::ShellScript
+  }
+}
+
+// Helper tools that ship next to the game, we never want them picked first
+fn is_helper_executable(path: &Path) -> bool {
+  let file_name = path
+    .file_name()
+    .unwrap_or_default()
+    .to_string_lossy()
+    .to_lowercase();
+  ["crash", "unins", "update", "setup"]
+    .iter()
+    .any(|helper| file_name.contains(helper))
+}
+
+// Pick the executable launch_mod should run for a mod on this platform
+pub fn resolve_launch_executable(mod_info: &ModInfo) -> Option<String> {
+  let mut candidates = mod_info.executable_candidates.clone();
+  if candidates.is_empty() {
+    // Mods loaded from the database don't carry their candidates
+    candidates = cached_executable_candidates(Path::new(&mod_info.path));
+  }
+
+  // Respect a manually chosen executable as long as it can run here
+  if let Some(exe_path) = &mod_info.executable_path {
+    let kind = candidates
+      .iter()
+      .find(|c| &c.path == exe_path)
+      .map(|c| c.kind)
+      .or_else(|| detect_executable_kind(Path::new(exe_path)));
+    match kind {
+      Some(kind) if is_native_executable(kind) => {
+        return Some(exe_path.clone());
+      }
+      None if Path::new(exe_path).exists() => {
+        return Some(exe_path.clone());
+      }
+      _ => {}
+    }
+  }
+
+  if
+    let Some(native) = candidates
+      .iter()
+      .find(|c| is_native_executable(c.kind))
+  {
+    return Some(native.path.clone());
+  }
+
+  // Nothing native, fall back to whatever we had before
+  mod_info.executable_path
+    .clone()
+    .or_else(|| candidates.first().map(|c| c.path.clone()))
+}
+
 // Extract icon from executable and convert to base64 data URL
 #[cfg(target_os = "windows")]
 pub fn extract_executable_icon(exe_path: &Path) -> Option<String> {
@@ -793,10 +1036,10 @@ pub fn create_mod_info(path: &str) -> Result<ModInfo, String> {
 
   // Find executable file(s)
   debug!("Searching for executables in: {}", path);
-  let executables = find_executables(path_obj);
-  let executable_path = executables
+  let executable_candidates = find_executable_candidates(path_obj);
+  let executable_path = executable_candidates
     .first()
-    .map(|p| p.to_string_lossy().to_string());
+    .map(|c| c.path.clone());
 
   if let Some(exe) = &executable_path {
     debug!("Found executable: {}", exe);
@@ -835,6 +1078,7 @@ pub fn create_mod_info(path: &str) -> Result<ModInfo, String> {
     description,
     display_order: Some(0), // default display order (top of the list)
     executable_path,
+    executable_candidates,
     icon_data,
     banner_data,
     logo_data,
@@ -849,6 +1093,9 @@ pub fn create_mod_info(path: &str) -> Result<ModInfo, String> {
     save_terminal_output: false, // Default to false, can be changed later
     current_session_id: None, // Initialize with None since mod is not running yet
     session_start_time: None, // Initialize with None since mod is not running yet
+    runner_id: None, // Use the default runner
+    launch_profile: LaunchProfile::default(),
+    hooks: LaunchHooks::default(),
   };
 
   Ok(mod_info)
@@ -0,0 +1,158 @@
+use log::{ debug, error, info, warn };
+use std::io::{ BufRead, BufReader };
+use std::path::Path;
+use std::process::{ Command, Stdio };
+use std::time::{ Duration, Instant };
+
+// Hooks that take longer than this get killed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(300);
+// How often to check whether a hook has finished
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// When a hook runs
+#[derive(Debug, Clone, Copy)]
+pub enum HookStage {
+  PreLaunch,
+  PostExit,
+}
+
+impl HookStage {
+  fn label(&self) -> &'static str {
+    match self {
+      HookStage::PreLaunch => "pre-launch",
+      HookStage::PostExit => "post-exit",
+    }
+  }
+}
+
+// Information about the mod a hook runs for, exposed to it as env vars
+#[derive(Debug, Clone)]
+pub struct HookContext {
+  pub mod_id: String,
+  pub mod_name: String,
+  pub mod_path: String,
+  pub session_id: String,
+  pub exit_code: Option<i32>, // Only known for post-exit hooks
+}
+
+/// Run hook commands one after the other, stopping at the first one that fails
+pub fn run_hooks(
+  stage: HookStage,
+  hooks: &[String],
+  context: &HookContext
+) -> Result<(), String> {
+  for hook in hooks.iter().filter(|h| !h.trim().is_empty()) {
+    run_hook(stage, hook, context)?;
+  }
+  Ok(())
+}
+
+fn run_hook(
+  stage: HookStage,
+  hook: &str,
+  context: &HookContext
+) -> Result<(), String> {
+  let label = stage.label();
+  info!("Running {} hook for mod {}: {}", label, context.mod_name, hook);
+  log_line(context, &format!("[{}] Running: {}", label, hook));
+
+  let mut command = shell_command(hook);
+  command
+    .env("FRIDAYLIGHT_MOD_ID", &context.mod_id)
+    .env("FRIDAYLIGHT_MOD_NAME", &context.mod_name)
+    .env("FRIDAYLIGHT_MOD_PATH", &context.mod_path)
+    .env("FRIDAYLIGHT_SESSION_ID", &context.session_id)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  if let Some(exit_code) = context.exit_code {
+    command.env("FRIDAYLIGHT_EXIT_CODE", exit_code.to_string());
+  }
+  if Path::new(&context.mod_path).is_dir() {
+    command.current_dir(&context.mod_path);
+  }
+
+  let mut child = command
+    .spawn()
+    .map_err(|e| format!("Failed to run {} hook '{}': {}", label, hook, e))?;
+
+  // Forward the hook's output into the session log. The readers aren't joined,
+  // programs started in the background by a hook can keep the pipes open
+  if let Some(stdout) = child.stdout.take() {
+    let context = context.clone();
+    std::thread::spawn(move || {
+      for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        log_line(&context, &format!("[{}] {}", label, line));
+      }
+    });
+  }
+  if let Some(stderr) = child.stderr.take() {
+    let context = context.clone();
+    std::thread::spawn(move || {
+      for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+        log_line(&context, &format!("[ERROR] [{}] {}", label, line));
+      }
+    });
+  }
+
+  let deadline = Instant::now() + HOOK_TIMEOUT;
+  let status = loop {
+    match child.try_wait() {
+      Ok(Some(status)) => {
+        break status;
+      }
+      Ok(None) if Instant::now() >= deadline => {
+        if let Err(e) = child.kill() {
+          warn!("Failed to kill {} hook: {}", label, e);
+        }
+        let _ = child.wait();
+        let err_msg = format!(
+          "{} hook '{}' timed out after {} seconds",
+          label,
+          hook,
+          HOOK_TIMEOUT.as_secs()
+        );
+        error!("{}", err_msg);
+        log_line(context, &format!("[ERROR] {}", err_msg));
+        return Err(err_msg);
+      }
+      Ok(None) => std::thread::sleep(HOOK_POLL_INTERVAL),
+      Err(e) => {
+        return Err(format!("Failed to wait for {} hook: {}", label, e));
+      }
+    }
+  };
+
+  if status.success() {
+    debug!("{} hook finished: {}", label, hook);
+    Ok(())
+  } else {
+    let err_msg = format!("{} hook '{}' failed with {}", label, hook, status);
+    error!("{}", err_msg);
+    log_line(context, &format!("[ERROR] {}", err_msg));
+    Err(err_msg)
+  }
+}
+
+fn log_line(context: &HookContext, line: &str) {
+  crate::terminaloutput::add_session_log(
+    &context.mod_id,
+    &context.session_id,
+    line
+  );
+}
+
+// Run hooks through the system shell so pipes, && and friends work
+#[cfg(target_os = "windows")]
+fn shell_command(hook: &str) -> Command {
+  let mut command = Command::new("cmd");
+  command.args(["/C", hook]);
+  command
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_command(hook: &str) -> Command {
+  let mut command = Command::new("sh");
+  command.args(["-c", hook]);
+  command
+}
@@ -0,0 +1,144 @@
+use crate::models::{ LaunchConflict, LaunchConflictReason, ModInfo, ModsState };
+use lazy_static::lazy_static;
+use log::{ debug, error, info, warn };
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use std::sync::Mutex;
+use tauri::Manager;
+
+// A launch waiting for a conflicting mod to exit
+#[derive(Debug, Clone)]
+struct QueuedLaunch {
+  mod_id: String,
+  waiting_for: String,
+}
+
+lazy_static! {
+  static ref LAUNCH_QUEUE: Mutex<Vec<QueuedLaunch>> = Mutex::new(Vec::new());
+}
+
+// Compare paths after resolving symlinks and `..`, so different spellings of a folder match
+fn normalize(path: &Path) -> PathBuf {
+  path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn executable_dir(mod_info: &ModInfo) -> Option<PathBuf> {
+  let executable = crate::filesystem::resolve_launch_executable(mod_info)?;
+  Path::new(&executable).parent().map(normalize)
+}
+
+fn mods_folder(mod_info: &ModInfo) -> Option<PathBuf> {
+  let engine = mod_info.engine.as_ref()?;
+  if engine.mods_folder != Some(true) {
+    return None;
+  }
+  let folder = engine.mods_folder_path.as_deref().unwrap_or("mods");
+  Some(normalize(&Path::new(&mod_info.path).join(folder)))
+}
+
+/// Find a running mod that shares an engine install with the given one
+pub fn find_conflict(
+  mod_id: &str,
+  mods: &HashMap<String, ModInfo>
+) -> Option<LaunchConflict> {
+  let mod_info = mods.get(mod_id)?;
+  let exe_dir = executable_dir(mod_info);
+  let mods_dir = mods_folder(mod_info);
+
+  for other in mods.values() {
+    if other.id == mod_id || other.process_id.is_none() {
+      continue;
+    }
+
+    let shared = match (&exe_dir, executable_dir(other)) {
+      (Some(dir), Some(other_dir)) if *dir == other_dir => {
+        Some((LaunchConflictReason::SharedExecutableDirectory, other_dir))
+      }
+      _ =>
+        match (&mods_dir, mods_folder(other)) {
+          (Some(dir), Some(other_dir)) if *dir == other_dir => {
+            Some((LaunchConflictReason::SharedModsFolder, other_dir))
+          }
+          _ => None,
+        }
+    };
+
+    if let Some((reason, path)) = shared {
+      debug!(
+        "Mod {} conflicts with running mod {}: {:?} at {}",
+        mod_info.name,
+        other.name,
+        reason,
+        path.display()
+      );
+      return Some(LaunchConflict {
+        conflicting_mod_id: other.id.clone(),
+        conflicting_mod_name: other.name.clone(),
+        reason,
+        shared_path: path.to_string_lossy().to_string(),
+      });
+    }
+  }
+
+  None
+}
+
+/// Launch a mod once a conflicting mod exits
+pub fn queue_launch(mod_id: &str, waiting_for: &str) {
+  let mut queue = LAUNCH_QUEUE.lock().unwrap();
+  queue.retain(|q| q.mod_id != mod_id);
+  queue.push(QueuedLaunch {
+    mod_id: mod_id.to_string(),
+    waiting_for: waiting_for.to_string(),
+  });
+  info!("Queued launch of mod {} until mod {} exits", mod_id, waiting_for);
+}
+
+/// Forget a queued launch, returns true if there was one
+pub fn cancel_queued_launch(mod_id: &str) -> bool {
+  let mut queue = LAUNCH_QUEUE.lock().unwrap();
+  let count = queue.len();
+  queue.retain(|q| q.mod_id != mod_id);
+  queue.len() != count
+}
+
+/// Start the launches that were waiting for a mod to exit
+pub fn launch_queued(exited_mod_id: &str) {
+  let ready = {
+    let mut queue = LAUNCH_QUEUE.lock().unwrap();
+    let (ready, waiting): (Vec<_>, Vec<_>) = queue
+      .drain(..)
+      .partition(|q| q.waiting_for == exited_mod_id);
+    *queue = waiting;
+    ready
+  };
+  if ready.is_empty() {
+    return;
+  }
+
+  let app_handle = match crate::app_handle::get_global_app_handle() {
+    Some(app_handle) => app_handle,
+    None => {
+      error!("App handle not available, can't start queued launches");
+      return;
+    }
+  };
+
+  // Launch from another thread, we may be called while the mod state is locked
+  std::thread::spawn(move || {
+    for queued in ready {
+      info!("Starting queued launch of mod {}", queued.mod_id);
+      let mods_state = app_handle.state::<ModsState>();
+      // Queue again if yet another mod is in the way
+      if
+        let Err(e) = crate::commands::launch_mod(
+          queued.mod_id.clone(),
+          Some(true),
+          mods_state
+        )
+      {
+        warn!("Queued launch of mod {} failed: {}", queued.mod_id, e);
+      }
+    }
+  });
+}
@@ -0,0 +1,107 @@
+use crate::models::{ ModInfo, ModLaunchSettings, ModsState, GLOBAL_MODS_STATE };
+use lazy_static::lazy_static;
+use log::{ debug, error, info };
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::Manager;
+
+// File in the app data directory holding every mod's launch settings
+const LAUNCH_SETTINGS_FILE: &str = "mod_launch_settings.json";
+
+// Serializes reads and writes of the settings file
+lazy_static! {
+  static ref LAUNCH_SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Load the launch settings of all mods (mod_id -> settings)
+fn load_all_launch_settings() -> HashMap<String, ModLaunchSettings> {
+  match crate::utils::get_app_data_file(LAUNCH_SETTINGS_FILE) {
+    Ok(path) => crate::utils::read_json_file(&path),
+    Err(e) => {
+      error!("Failed to resolve launch settings file: {}", e);
+      HashMap::new()
+    }
+  }
+}
+
+/// Save the launch settings of all mods
+fn save_all_launch_settings(
+  settings: &HashMap<String, ModLaunchSettings>
+) -> Result<(), String> {
+  let path = crate::utils::get_app_data_file(LAUNCH_SETTINGS_FILE)?;
+  crate::utils::write_json_file(&path, settings)
+}
+
+/// Get the launch settings for a mod, or the defaults if it has none
+pub fn get_mod_launch_settings(mod_id: &str) -> ModLaunchSettings {
+  let _guard = LAUNCH_SETTINGS_LOCK.lock().unwrap();
+  load_all_launch_settings().remove(mod_id).unwrap_or_default()
+}
+
+/// Change the launch settings for a mod and persist them
+pub fn update_mod_launch_settings<F: FnOnce(&mut ModLaunchSettings)>(
+  mod_id: &str,
+  update: F
+) -> Result<ModLaunchSettings, String> {
+  let settings = {
+    let _guard = LAUNCH_SETTINGS_LOCK.lock().unwrap();
+    let mut all_settings = load_all_launch_settings();
+    let mut settings = all_settings.remove(mod_id).unwrap_or_default();
+    update(&mut settings);
+
+    // Don't keep entries around for mods that only use the defaults
+    if settings != ModLaunchSettings::default() {
+      all_settings.insert(mod_id.to_string(), settings.clone());
+    }
+    save_all_launch_settings(&all_settings)?;
+    settings
+  };
+
+  debug!("Saved launch settings for mod {}: {:?}", mod_id, settings);
+  apply_to_mod_states(mod_id, &settings);
+  Ok(settings)
+}
+
+/// Forget the launch settings of a mod
+pub fn remove_mod_launch_settings(mod_id: &str) {
+  let _guard = LAUNCH_SETTINGS_LOCK.lock().unwrap();
+  let mut all_settings = load_all_launch_settings();
+  if all_settings.remove(mod_id).is_some() {
+    if let Err(e) = save_all_launch_settings(&all_settings) {
+      error!("Failed to remove launch settings for mod {}: {}", mod_id, e);
+    } else {
+      info!("Removed launch settings for mod {}", mod_id);
+    }
+  }
+}
+
+/// Copy the stored launch settings onto a ModInfo
+pub fn apply_launch_settings(mod_info: &mut ModInfo) {
+  let settings = get_mod_launch_settings(&mod_info.id);
+  apply_settings(mod_info, &settings);
+}
+
+fn apply_settings(mod_info: &mut ModInfo, settings: &ModLaunchSettings) {
+  mod_info.runner_id = settings.runner_id.clone();
+  mod_info.launch_profile = settings.launch_profile.clone();
+  mod_info.hooks = settings.hooks.clone();
+}
+
+// Update the mod in both the Tauri state and the global state
+fn apply_to_mod_states(mod_id: &str, settings: &ModLaunchSettings) {
+  if let Ok(mut mods) = GLOBAL_MODS_STATE.lock() {
+    if let Some(mod_info) = mods.get_mut(mod_id) {
+      apply_settings(mod_info, settings);
+    }
+  }
+
+  if let Some(app_handle) = crate::app_handle::get_global_app_handle() {
+    if let Some(mods_state) = app_handle.try_state::<ModsState>() {
+      if let Ok(mut mods) = mods_state.0.lock() {
+        if let Some(mod_info) = mods.get_mut(mod_id) {
+          apply_settings(mod_info, settings);
+        }
+      }
+    }
+  }
+}
@@ -1,17 +1,57 @@
+// Parsing ANSI colors in terminal output
+pub mod ansi;
+// Bug report bundles for mod sessions
+pub mod bugreport;
 // Commands to be used in the frontend
 pub mod commands;
+// Crash detection and crash report collection
+pub mod crashreports;
+// Staging downloads in the app cache and the archive library
+pub mod downloadcache;
+// Download queue, pausing and cancelling downloads
+pub mod downloadqueue;
+// Backend settings for downloads
+pub mod downloadsettings;
 // Download related functions, used in conjunction w/ Gamebanana to download mods
 pub mod download;
 // File system related functions (Saving/loading mods)
 pub mod filesystem;
 // GameBanana API interfacing
 pub mod gamebanana;
+// Pre-launch and post-exit hook commands
+pub mod hooks;
+// Detecting mods that can't run at the same time
+pub mod launchconflicts;
+// Per-mod launch settings stored by the backend
+pub mod launchsettings;
 // Logging
 pub mod logger;
+// Pruning old session logs
+pub mod logretention;
+// Searching the logs of all sessions
+pub mod logsearch;
+// Parsing terminal output into structured log records
+pub mod logparser;
+// Batched live log events for the frontend
+pub mod logstream;
 // Interfaces, types, and structures used throughout the app
 pub mod models;
 // Mod utility functions
 pub mod modutils;
+// Downloads that can be resumed after failing partway through
+pub mod partialdownloads;
+// Stopping and monitoring launched mod processes
+pub mod processes;
+// CPU and memory usage sampling for running mods
+pub mod resourcemonitor;
+// Persisted running mods, re-attached after restarts
+pub mod runningmods;
+// Wine/Proton runners for launching Windows builds
+pub mod runners;
+// History of finished sessions and playtime stats
+pub mod sessionhistory;
+// Index of every saved session
+pub mod sessionindex;
 // Terminal output capture and display
 pub mod terminaloutput;
 // Other utility functions
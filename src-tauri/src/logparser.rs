@@ -0,0 +1,109 @@
+use crate::models::{ LogLevel, LogRecord, LogStream };
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+  // Haxe traces, source/PlayState.hx:123: message
+  static ref HAXE_TRACE: Regex = Regex::new(
+    r"^(?P<file>(?:[A-Za-z]:)?[^:\s][^:]*\.hx):(?P<line>\d+):\s?(?P<message>.*)$"
+  ).unwrap();
+  // Lua errors, mods/scripts/script.lua:12: attempt to call a nil value
+  // Psych Engine puts the callback name in front, ERROR ("onCreate"): mods/...
+  static ref LUA_ERROR: Regex = Regex::new(
+    r#"^(?:ERROR \("[^"]*"\):\s*)?(?P<file>(?:[A-Za-z]:)?[^:\s][^:]*\.lua):(?P<line>\d+):\s?(?P<message>.*)$"#
+  ).unwrap();
+  // HScript errors, hscript:12: Unknown variable foo or script.hxs:12: ...
+  static ref HSCRIPT_ERROR: Regex = Regex::new(
+    r"^(?P<file>hscript|(?:[A-Za-z]:)?[^:\s][^:]*\.(?:hxs|hsc|hscript|hxc)):(?P<line>\d+):\s?(?P<message>.*)$"
+  ).unwrap();
+  // Level tags at the start of a message, [WARNING] foo or ERROR: foo
+  static ref LEVEL_TAG: Regex = Regex::new(
+    r"(?i)^\[?(?P<level>error|err|fatal|warning|warn|info|debug|trace)\]?:?(?:\s|$)"
+  ).unwrap();
+}
+
+/// Parse a line printed by a running mod
+pub fn parse_output_line(stream: LogStream, line: &str) -> LogRecord {
+  // Patterns are matched against the text without colors
+  let (plain_text, spans) = crate::ansi::parse_ansi(line);
+  let text = plain_text.as_str();
+
+  let default_level = match stream {
+    LogStream::Stderr => LogLevel::Error,
+    _ => LogLevel::Info,
+  };
+  let mut record = LogRecord {
+    timestamp: Some(chrono::Utc::now().timestamp_millis()),
+    stream,
+    level: default_level,
+    source_file: None,
+    line: None,
+    message: text.to_string(),
+    text: text.to_string(),
+    spans,
+  };
+
+  // Script errors are always errors, traces only if they say so
+  let source = if let Some(captures) = LUA_ERROR.captures(text) {
+    Some((captures, LogLevel::Error))
+  } else if let Some(captures) = HSCRIPT_ERROR.captures(text) {
+    Some((captures, LogLevel::Error))
+  } else {
+    HAXE_TRACE.captures(text).map(|captures| (captures, default_level))
+  };
+
+  if let Some((captures, level)) = source {
+    record.source_file = Some(captures["file"].to_string());
+    record.line = captures["line"].parse().ok();
+    record.message = captures["message"].to_string();
+    record.level = level;
+  }
+
+  if let Some(level) = level_from_tag(&record.message) {
+    record.level = level;
+  }
+  record
+}
+
+/// Make a record for a message written by Fridaylight itself,
+/// messages starting with [ERROR] are errors
+pub fn system_record(line: &str) -> LogRecord {
+  let (level, line) = match line.strip_prefix("[ERROR] ") {
+    Some(rest) => (LogLevel::Error, rest),
+    None => (LogLevel::Info, line),
+  };
+  // Hooks can print colors too
+  let (text, spans) = crate::ansi::parse_ansi(line);
+  LogRecord {
+    timestamp: Some(chrono::Utc::now().timestamp_millis()),
+    stream: LogStream::System,
+    level,
+    source_file: None,
+    line: None,
+    message: text.to_string(),
+    text: text.to_string(),
+    spans,
+  }
+}
+
+/// Parse a line from a plain output.log, these don't say when they were printed
+/// or whether they came from the game or from Fridaylight
+pub fn parse_saved_line(line: &str) -> LogRecord {
+  let mut record = match line.strip_prefix("[ERROR] ") {
+    Some(rest) => parse_output_line(LogStream::Stderr, rest),
+    None => parse_output_line(LogStream::Stdout, line),
+  };
+  record.timestamp = None;
+  record
+}
+
+fn level_from_tag(message: &str) -> Option<LogLevel> {
+  let captures = LEVEL_TAG.captures(message)?;
+  let level = match captures["level"].to_lowercase().as_str() {
+    "error" | "err" | "fatal" => LogLevel::Error,
+    "warning" | "warn" => LogLevel::Warning,
+    "info" => LogLevel::Info,
+    _ => LogLevel::Debug,
+  };
+  Some(level)
+}
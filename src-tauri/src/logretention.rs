@@ -0,0 +1,225 @@
+use crate::models::{
+  LogPruneReason,
+  LogRetentionPolicy,
+  LogRetentionReport,
+  PrunedSession,
+  SessionInfo,
+};
+use lazy_static::lazy_static;
+use log::{ error, info, warn };
+use std::collections::{ HashMap, HashSet };
+use std::path::{ Path, PathBuf };
+use std::sync::Mutex;
+
+// File in the app data directory holding the retention policy
+const LOG_RETENTION_FILE: &str = "log_retention.json";
+
+lazy_static! {
+  // The policy, loaded from disk the first time it's needed. Also serializes
+  // writes of the policy file
+  static ref CACHED_POLICY: Mutex<Option<LogRetentionPolicy>> = Mutex::new(None);
+  // Only one pruning pass at a time
+  static ref PRUNE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// A session folder on disk
+struct SavedSession {
+  info: SessionInfo,
+  path: PathBuf,
+  size_bytes: u64,
+}
+
+/// Get the log retention policy
+pub fn get_policy() -> LogRetentionPolicy {
+  let mut cached = CACHED_POLICY.lock().unwrap();
+  if let Some(policy) = cached.as_ref() {
+    return policy.clone();
+  }
+
+  let policy = match crate::utils::get_app_data_file(LOG_RETENTION_FILE) {
+    Ok(path) => crate::utils::read_json_file(&path),
+    Err(e) => {
+      error!("Failed to resolve log retention file: {}", e);
+      LogRetentionPolicy::default()
+    }
+  };
+  *cached = Some(policy.clone());
+  policy
+}
+
+/// Save the log retention policy
+pub fn set_policy(policy: &LogRetentionPolicy) -> Result<(), String> {
+  let mut cached = CACHED_POLICY.lock().unwrap();
+  let path = crate::utils::get_app_data_file(LOG_RETENTION_FILE)?;
+  crate::utils::write_json_file(&path, policy)?;
+  *cached = Some(policy.clone());
+  info!("Saved log retention policy: {:?}", policy);
+  Ok(())
+}
+
+/// How many lines to keep in memory for mods that don't save their output
+pub fn get_max_unsaved_logs_in_memory() -> usize {
+  get_policy()
+    .max_unsaved_lines_in_memory
+    .unwrap_or(crate::terminaloutput::DEFAULT_MAX_UNSAVED_LOGS_IN_MEMORY)
+    .max(1)
+}
+
+/// Remove the session folders that go over the retention policy.
+/// Sessions that are still running are never removed
+pub fn enforce_policy() -> LogRetentionReport {
+  let policy = get_policy();
+  let has_limits =
+    policy.max_sessions_per_mod.is_some() ||
+    policy.max_age_days.is_some() ||
+    policy.max_total_size_mb.is_some();
+  if !has_limits {
+    return LogRetentionReport::default();
+  }
+
+  let _guard = PRUNE_LOCK.lock().unwrap();
+  let running = crate::terminaloutput
+    ::get_running_session_infos()
+    .into_iter()
+    .map(|s| s.session_id)
+    .collect::<HashSet<_>>();
+
+  let mut sessions = find_saved_sessions();
+  // Newest first, so the oldest ones are at the end
+  sessions.sort_by(|a, b| b.info.start_time.cmp(&a.info.start_time));
+
+  let mut to_prune: HashMap<String, LogPruneReason> = HashMap::new();
+
+  if let Some(max_sessions) = policy.max_sessions_per_mod {
+    let mut kept_per_mod: HashMap<&str, usize> = HashMap::new();
+    for session in &sessions {
+      let kept = kept_per_mod.entry(session.info.mod_id.as_str()).or_insert(0);
+      if *kept < max_sessions || running.contains(&session.info.session_id) {
+        *kept += 1;
+      } else {
+        to_prune.insert(
+          session.info.session_id.clone(),
+          LogPruneReason::TooManySessions
+        );
+      }
+    }
+  }
+
+  if let Some(max_age_days) = policy.max_age_days {
+    let cutoff =
+      chrono::Utc::now().timestamp() - (max_age_days as i64) * 24 * 60 * 60;
+    for session in &sessions {
+      let last_active = session.info.end_time.unwrap_or(session.info.start_time);
+      if last_active < cutoff {
+        to_prune
+          .entry(session.info.session_id.clone())
+          .or_insert(LogPruneReason::TooOld);
+      }
+    }
+  }
+
+  if let Some(max_total_size_mb) = policy.max_total_size_mb {
+    let max_total_size = max_total_size_mb * 1024 * 1024;
+    let mut total_size = sessions
+      .iter()
+      .filter(|s| !to_prune.contains_key(&s.info.session_id))
+      .map(|s| s.size_bytes)
+      .sum::<u64>();
+    for session in sessions.iter().rev() {
+      if total_size <= max_total_size {
+        break;
+      }
+      let id = &session.info.session_id;
+      if to_prune.contains_key(id) || running.contains(id) {
+        continue;
+      }
+      to_prune.insert(id.clone(), LogPruneReason::TotalSizeExceeded);
+      total_size -= session.size_bytes;
+    }
+  }
+
+  let mut report = LogRetentionReport::default();
+  for session in sessions {
+    let reason = match to_prune.get(&session.info.session_id) {
+      Some(reason) => *reason,
+      None => {
+        continue;
+      }
+    };
+    if running.contains(&session.info.session_id) {
+      continue;
+    }
+
+    let result = crate::terminaloutput::delete_session_folder(
+      &session.info.mod_id,
+      &session.info.session_id
+    );
+    if let Err(e) = result {
+      error!("Failed to prune session folder {}: {}", session.path.display(), e);
+      continue;
+    }
+    info!(
+      "Pruned session {} of mod {} ({:?}, {} bytes)",
+      session.info.session_id,
+      session.info.mod_name,
+      reason,
+      session.size_bytes
+    );
+
+    report.freed_bytes += session.size_bytes;
+    report.pruned_sessions.push(PrunedSession {
+      mod_id: session.info.mod_id,
+      mod_name: session.info.mod_name,
+      session_id: session.info.session_id,
+      start_time: session.info.start_time,
+      size_bytes: session.size_bytes,
+      reason,
+    });
+  }
+
+  if !report.pruned_sessions.is_empty() {
+    info!(
+      "Pruned {} sessions, freed {} bytes",
+      report.pruned_sessions.len(),
+      report.freed_bytes
+    );
+    if let Err(e) = crate::app_handle::emit_event("mod-logs-pruned", report.clone()) {
+      warn!("Failed to emit mod-logs-pruned event: {}", e);
+    }
+  }
+  report
+}
+
+// Every session saved to disk along with its folder
+fn find_saved_sessions() -> Vec<SavedSession> {
+  crate::sessionindex
+    ::list_all_sessions()
+    .into_iter()
+    .filter_map(|info| {
+      let path = crate::terminaloutput::get_session_dir_for(&info)?;
+      Some(SavedSession {
+        size_bytes: dir_size(&path),
+        info,
+        path,
+      })
+    })
+    .collect()
+}
+
+fn dir_size(path: &Path) -> u64 {
+  let mut size = 0;
+  if let Ok(entries) = std::fs::read_dir(path) {
+    for entry in entries.filter_map(|e| e.ok()) {
+      match entry.metadata() {
+        Ok(metadata) if metadata.is_dir() => {
+          size += dir_size(&entry.path());
+        }
+        Ok(metadata) => {
+          size += metadata.len();
+        }
+        Err(_) => {}
+      }
+    }
+  }
+  size
+}
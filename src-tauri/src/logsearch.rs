@@ -0,0 +1,206 @@
+use crate::models::{
+  LogRecord,
+  LogSearchMatch,
+  LogSearchQuery,
+  LogSearchResults,
+  SessionInfo,
+};
+use log::{ debug, info };
+use regex::{ Regex, RegexBuilder };
+use std::collections::{ HashSet, VecDeque };
+
+// Page size when the query doesn't set one
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+const MAX_CONTEXT_LINES: usize = 20;
+// Matches past the requested page stop being counted after this many
+const MAX_COUNTED_MATCHES: usize = 10000;
+
+/// Search the logs of running and saved sessions, newest sessions first
+pub fn search_logs(query: &LogSearchQuery) -> Result<LogSearchResults, String> {
+  let matcher = build_matcher(query)?;
+  let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+  let context_lines = query.context_lines.min(MAX_CONTEXT_LINES);
+
+  info!(
+    "Searching logs for '{}' (mod: {:?}, offset: {}, limit: {})",
+    query.query,
+    query.mod_id,
+    query.offset,
+    limit
+  );
+
+  let mut matches: Vec<LogSearchMatch> = Vec::new();
+  let mut total = 0;
+  'sessions: for session in find_sessions(query) {
+    let session_first_match = matches.len();
+    // Lines before the current one, and matches still waiting for the lines after them
+    let mut before = VecDeque::with_capacity(context_lines);
+    let mut waiting_for_context: Vec<usize> = Vec::new();
+    let mut disk_count = 0;
+
+    for (index, (from_disk, record)) in session_records(&session).enumerate() {
+      disk_count += from_disk as usize;
+      let line = record.to_log_line();
+      waiting_for_context.retain(|&match_index| {
+        let context_after = &mut matches[match_index].context_after;
+        context_after.push(line.clone());
+        context_after.len() < context_lines
+      });
+
+      if is_match(query, &matcher, &record) {
+        total += 1;
+        // Only build the matches on the requested page
+        if total > query.offset && matches.len() < limit {
+          if context_lines > 0 {
+            waiting_for_context.push(matches.len());
+          }
+          matches.push(LogSearchMatch {
+            mod_id: session.mod_id.clone(),
+            mod_name: session.mod_name.clone(),
+            session_id: session.session_id.clone(),
+            session_start_time: session.start_time,
+            line_number: index + 1,
+            record,
+            context_before: before.iter().cloned().collect(),
+            context_after: Vec::new(),
+          });
+        }
+      }
+
+      // Past the requested page the matches only get counted, up to a point
+      if
+        matches.len() >= limit &&
+        waiting_for_context.is_empty() &&
+        total >= MAX_COUNTED_MATCHES
+      {
+        fix_line_numbers(&mut matches[session_first_match..], &session, disk_count);
+        break 'sessions;
+      }
+
+      if context_lines > 0 {
+        if before.len() == context_lines {
+          before.pop_front();
+        }
+        before.push_back(line);
+      }
+    }
+
+    fix_line_numbers(&mut matches[session_first_match..], &session, disk_count);
+  }
+
+  debug!("Log search found {} matches", total);
+  Ok(LogSearchResults {
+    has_more: query.offset + matches.len() < total,
+    matches,
+    total,
+    offset: query.offset,
+  })
+}
+
+// Line numbers count from the start of the session like the offsets of
+// get_session_logs_since do, lines moved to disk or dropped included
+fn fix_line_numbers(
+  matches: &mut [LogSearchMatch],
+  session: &SessionInfo,
+  disk_count: usize
+) {
+  let first_offset = crate::terminaloutput
+    ::get_evicted_log_count(&session.session_id)
+    .saturating_sub(disk_count);
+  for search_match in matches {
+    search_match.line_number += first_offset;
+  }
+}
+
+fn build_matcher(query: &LogSearchQuery) -> Result<Regex, String> {
+  if query.query.is_empty() {
+    return Err("Search query is empty".to_string());
+  }
+
+  let pattern = if query.regex {
+    query.query.clone()
+  } else {
+    regex::escape(&query.query)
+  };
+  RegexBuilder::new(&pattern)
+    .case_insensitive(!query.case_sensitive)
+    .build()
+    .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+// Sessions that can have matches, saved ones and the ones still running
+fn find_sessions(query: &LogSearchQuery) -> Vec<SessionInfo> {
+  let mut sessions = crate::terminaloutput::get_running_session_infos();
+  let running_ids = sessions
+    .iter()
+    .map(|s| s.session_id.clone())
+    .collect::<HashSet<_>>();
+  sessions.extend(
+    crate::terminaloutput
+      ::load_all_session_infos_from_disk()
+      .into_iter()
+      .filter(|s| !running_ids.contains(&s.session_id))
+  );
+
+  sessions.retain(|session| {
+    let for_mod = query.mod_id
+      .as_ref()
+      .map(|id| &session.mod_id == id)
+      .unwrap_or(true);
+    // Skip sessions that don't overlap the time range at all
+    let end_time = session.end_time.unwrap_or_else(||
+      chrono::Utc::now().timestamp()
+    );
+    let after_since = query.since
+      .map(|since| end_time >= since)
+      .unwrap_or(true);
+    let before_until = query.until
+      .map(|until| session.start_time <= until)
+      .unwrap_or(true);
+    for_mod && after_since && before_until
+  });
+  sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+  sessions
+}
+
+// The records of a session one at a time, the ones saved to disk first and
+// whether each came from disk
+fn session_records(
+  session: &SessionInfo
+) -> impl Iterator<Item = (bool, LogRecord)> {
+  let memory_records = crate::terminaloutput::get_memory_records(
+    &session.mod_id,
+    &session.session_id
+  );
+  crate::terminaloutput
+    ::iter_session_logs_from_disk(session)
+    .map(|record| (true, record))
+    .chain(memory_records.into_iter().map(|record| (false, record)))
+}
+
+fn is_match(
+  query: &LogSearchQuery,
+  matcher: &Regex,
+  record: &LogRecord
+) -> bool {
+  if !query.streams.is_empty() && !query.streams.contains(&record.stream) {
+    return false;
+  }
+  if !query.levels.is_empty() && !query.levels.contains(&record.level) {
+    return false;
+  }
+
+  // Logs saved by older versions have no timestamp, for those the session
+  // overlapping the time range is as close as it gets
+  if let Some(timestamp) = record.timestamp.map(|ms| ms / 1000) {
+    if query.since.map(|since| timestamp < since).unwrap_or(false) {
+      return false;
+    }
+    if query.until.map(|until| timestamp > until).unwrap_or(false) {
+      return false;
+    }
+  }
+
+  matcher.is_match(&record.text)
+}
@@ -0,0 +1,100 @@
+use crate::models::{ LogRecord, ModLogLines };
+use lazy_static::lazy_static;
+use log::{ debug, warn };
+use std::collections::HashMap;
+use std::sync::{ Mutex, Once };
+use std::time::Duration;
+use tauri::Emitter;
+
+// At most one mod-log-line event per session in this interval
+const EVENT_INTERVAL: Duration = Duration::from_millis(100);
+// Lines past this in one interval are skipped, the viewer can fetch them
+// with get_session_logs_since
+const MAX_LINES_PER_EVENT: usize = 500;
+
+// Lines waiting to be sent for a session
+struct PendingLines {
+  mod_id: String,
+  offset: usize, // Offset of the first line in records
+  records: Vec<LogRecord>,
+  skipped: usize,
+}
+
+lazy_static! {
+  // session_id -> lines not sent yet
+  static ref PENDING_LINES: Mutex<HashMap<String, PendingLines>> = Mutex::new(
+    HashMap::new()
+  );
+}
+
+static START_FLUSHER: Once = Once::new();
+
+/// Queue a new log line to be sent to the frontend with the next batch
+pub fn queue_line(
+  mod_id: &str,
+  session_id: &str,
+  offset: usize,
+  record: &LogRecord
+) {
+  START_FLUSHER.call_once(|| {
+    std::thread::spawn(flush_loop);
+  });
+
+  let mut pending = PENDING_LINES.lock().unwrap();
+  let batch = pending
+    .entry(session_id.to_string())
+    .or_insert_with(|| PendingLines {
+      mod_id: mod_id.to_string(),
+      offset,
+      records: Vec::new(),
+      skipped: 0,
+    });
+
+  // Keep the newest lines, those are the ones a live view cares about
+  if batch.records.len() >= MAX_LINES_PER_EVENT {
+    batch.records.remove(0);
+    batch.offset += 1;
+    batch.skipped += 1;
+  }
+  batch.records.push(record.clone());
+}
+
+fn flush_loop() {
+  debug!("Starting mod log event flusher");
+  loop {
+    std::thread::sleep(EVENT_INTERVAL);
+
+    let batches = std::mem::take(&mut *PENDING_LINES.lock().unwrap());
+    if batches.is_empty() {
+      continue;
+    }
+
+    let app_handle = match crate::app_handle::get_global_app_handle() {
+      Some(app_handle) => app_handle,
+      None => {
+        continue;
+      }
+    };
+    // Not using app_handle::emit_event, it logs every payload
+    for (session_id, batch) in batches {
+      if batch.skipped > 0 {
+        debug!(
+          "Skipped {} log lines of session {} in the last event",
+          batch.skipped,
+          session_id
+        );
+      }
+      let payload = ModLogLines {
+        mod_id: batch.mod_id,
+        session_id,
+        offset: batch.offset,
+        next_offset: batch.offset + batch.records.len(),
+        records: batch.records,
+        skipped: batch.skipped,
+      };
+      if let Err(e) = app_handle.emit("mod-log-line", payload) {
+        warn!("Failed to emit mod-log-line event: {}", e);
+      }
+    }
+  }
+}
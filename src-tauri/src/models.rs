@@ -1,5 +1,5 @@
 use serde::{ Deserialize, Serialize };
-use std::collections::HashMap;
+use std::collections::{ HashMap, HashSet };
 use std::sync::Mutex;
 use std::fmt;
 
@@ -908,6 +908,82 @@ pub struct ContributorGroup {
   pub members: Vec<Contributor>,
 }
 
+// The kind of launchable file found in a mod folder
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutableKind {
+  WindowsExe, // .exe file
+  LinuxElf, // Native ELF binary with the execute bit set
+  AppImage, // .AppImage bundle with the execute bit set
+  ShellScript, // .sh launcher with the execute bit set
+}
+
+// An executable found in a mod folder, ranked for the current platform
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ExecutableCandidate {
+  pub path: String,
+  pub kind: ExecutableKind,
+}
+
+// Compatibility layers that can run Windows builds on other platforms
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunnerKind {
+  Wine,
+  Proton,
+}
+
+// A configured Wine/Proton install used to launch Windows-only mods
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RunnerConfig {
+  pub id: String,
+  pub name: String,
+  pub kind: RunnerKind,
+  pub path: String, // Path to the wine binary or the proton script
+  pub prefix: Option<String>, // WINEPREFIX, or the compat data path for Proton
+  #[serde(default)]
+  pub env: HashMap<String, String>, // Extra environment variables
+  #[serde(default)]
+  pub dll_overrides: HashMap<String, String>, // dll name -> mode, e.g. "d3d9" -> "n,b"
+}
+
+// Stored list of runners along with the default one
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RunnersConfig {
+  pub runners: Vec<RunnerConfig>,
+  pub default_runner_id: Option<String>,
+}
+
+// Per-mod launch settings that the backend owns and persists itself,
+// since they aren't stored in the frontend database
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ModLaunchSettings {
+  pub runner_id: Option<String>,
+  #[serde(default)]
+  pub launch_profile: LaunchProfile,
+  #[serde(default)]
+  pub hooks: LaunchHooks,
+}
+
+// Shell commands run before a mod starts and after it exits
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct LaunchHooks {
+  #[serde(default)]
+  pub pre_launch: Vec<String>,
+  #[serde(default)]
+  pub post_exit: Vec<String>,
+}
+
+// Extra arguments, environment and working directory used when launching a mod
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct LaunchProfile {
+  #[serde(default)]
+  pub args: Vec<String>,
+  #[serde(default)]
+  pub env: HashMap<String, String>,
+  pub working_dir: Option<String>, // Relative paths are resolved from the mod folder, exe folder if None
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ModInfo {
   pub id: String,
@@ -916,6 +992,8 @@ pub struct ModInfo {
   pub metadata_version: Option<u32>,
   pub description: Option<String>,
   pub executable_path: Option<String>,
+  #[serde(default)]
+  pub executable_candidates: Vec<ExecutableCandidate>, // All executables found, best match for this platform first
   pub icon_data: Option<String>, // Base64 encoded icon data
   pub banner_data: Option<String>, // Base64 encoded banner image data
   pub logo_data: Option<String>, // Base64 encoded logo image data
@@ -931,6 +1009,11 @@ pub struct ModInfo {
   pub save_terminal_output: bool, // Whether to save terminal output
   pub current_session_id: Option<String>, // UUID for current session
   pub session_start_time: Option<i64>, // Unix timestamp when session started
+  pub runner_id: Option<String>, // Wine/Proton runner used for Windows builds, default runner if None
+  #[serde(default)]
+  pub launch_profile: LaunchProfile, // Arguments, env vars and working directory for launching
+  #[serde(default)]
+  pub hooks: LaunchHooks, // Commands run before launching and after exiting
 }
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ModInfoGBData {
@@ -990,6 +1073,24 @@ pub struct ModDisableResult {
   pub message: String,
 }
 
+// How a running mod ended up being stopped
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopMethod {
+  Graceful, // Exited after being asked to (SIGTERM / WM_CLOSE)
+  Forced, // Had to be killed after the grace period
+  AlreadyExited, // Was gone before we got to it
+  ChildrenStopped, // Was gone before we got to it, but left processes that had to be stopped
+}
+
+// Response structure for stopping a running mod
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModStopResult {
+  pub success: bool,
+  pub method: Option<StopMethod>,
+  pub message: String,
+}
+
 // Session information structure for terminal output tracking
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SessionInfo {
@@ -999,6 +1100,304 @@ pub struct SessionInfo {
   pub start_time: i64,
   pub end_time: Option<i64>,
   pub exit_code: Option<i32>,
+  #[serde(default)]
+  pub command_line: Option<String>, // The command the mod was actually launched with
+  #[serde(default)]
+  pub crashed: bool,
+  #[serde(default)]
+  pub crash_summary: Option<String>, // Short description of what went wrong
+  #[serde(default)]
+  pub crash_files: Vec<String>, // Crash files copied into the session folder
+  #[serde(default)]
+  pub peak_memory_bytes: Option<u64>,
+  #[serde(default)]
+  pub average_cpu_percent: Option<f64>,
+}
+
+// A page of saved sessions, newest first
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionPage {
+  pub sessions: Vec<SessionInfo>,
+  pub total: usize, // Sessions across all pages
+  pub offset: usize,
+  pub has_more: bool,
+}
+
+// Where a log line came from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogStream {
+  Stdout,
+  Stderr,
+  System, // Written by Fridaylight itself (launch info, hooks, exit codes...)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+  Debug,
+  Info,
+  Warning,
+  Error,
+}
+
+// A single parsed line of a mod's terminal output
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogRecord {
+  pub timestamp: Option<i64>, // Milliseconds, unknown for logs saved by older versions
+  pub stream: LogStream,
+  pub level: LogLevel,
+  pub source_file: Option<String>, // e.g. source/PlayState.hx for Haxe traces
+  pub line: Option<u32>,
+  pub message: String,
+  pub text: String, // The whole line as it was printed, without escape sequences
+  #[serde(default)]
+  pub spans: Vec<StyledSpan>, // Only set when the line was printed with colors
+}
+
+// A piece of a log line printed with the same style. Colors are the basic
+// color names (red, bright_red...) or #rrggbb
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StyledSpan {
+  pub text: String,
+  pub color: Option<String>,
+  pub background: Option<String>,
+  pub bold: bool,
+  pub italic: bool,
+  pub underline: bool,
+}
+
+impl LogRecord {
+  /// The line as shown in the plain terminal output
+  pub fn to_log_line(&self) -> String {
+    let is_error =
+      self.stream == LogStream::Stderr ||
+      (self.stream == LogStream::System && self.level == LogLevel::Error);
+    if is_error {
+      format!("[ERROR] {}", self.text)
+    } else {
+      self.text.clone()
+    }
+  }
+}
+
+// Payload of the mod-log-line event, the lines printed since the last one
+#[derive(Debug, Serialize, Clone)]
+pub struct ModLogLines {
+  pub mod_id: String,
+  pub session_id: String,
+  pub offset: usize, // Offset of the first record in the session
+  pub next_offset: usize,
+  pub records: Vec<LogRecord>,
+  pub skipped: usize, // Lines left out before offset, fetch them with get_session_logs_since
+}
+
+// Logs of a session, as shown in the plain terminal output
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SessionLogs {
+  pub lines: Vec<String>,
+  pub truncated_lines: usize, // Lines dropped from memory because the mod doesn't save its output
+}
+
+// Logs of a session from a given offset on
+#[derive(Debug, Serialize, Clone)]
+pub struct SessionLogsSince {
+  pub records: Vec<LogRecord>,
+  pub next_offset: usize, // Pass this as the offset next time
+  pub truncated_lines: usize, // Lines dropped from memory that can't be fetched anymore
+}
+
+// Search through saved and running sessions' logs
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogSearchQuery {
+  pub query: String,
+  #[serde(default)]
+  pub regex: bool, // Treat query as a regex instead of plain text
+  #[serde(default)]
+  pub case_sensitive: bool,
+  #[serde(default)]
+  pub mod_id: Option<String>, // Search all mods when not set
+  #[serde(default)]
+  pub streams: Vec<LogStream>, // Any stream when empty
+  #[serde(default)]
+  pub levels: Vec<LogLevel>, // Any level when empty
+  #[serde(default)]
+  pub since: Option<i64>, // Unix timestamps, like session start times
+  #[serde(default)]
+  pub until: Option<i64>,
+  #[serde(default)]
+  pub context_lines: usize,
+  #[serde(default)]
+  pub offset: usize,
+  #[serde(default)]
+  pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LogSearchMatch {
+  pub mod_id: String,
+  pub mod_name: String,
+  pub session_id: String,
+  pub session_start_time: i64,
+  pub line_number: usize, // Starts at 1
+  pub record: LogRecord,
+  pub context_before: Vec<String>,
+  pub context_after: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LogSearchResults {
+  pub matches: Vec<LogSearchMatch>,
+  pub total: usize, // Matches across all pages, counting stops at 10000
+  pub offset: usize,
+  pub has_more: bool,
+}
+
+// Limits on how many session logs are kept, nothing is pruned when unset
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct LogRetentionPolicy {
+  #[serde(default)]
+  pub max_sessions_per_mod: Option<usize>,
+  #[serde(default)]
+  pub max_age_days: Option<u64>,
+  #[serde(default)]
+  pub max_total_size_mb: Option<u64>,
+  #[serde(default)]
+  pub max_unsaved_lines_in_memory: Option<usize>, // For mods that don't save their output
+}
+
+// Which limit got a session pruned
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogPruneReason {
+  TooManySessions,
+  TooOld,
+  TotalSizeExceeded,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PrunedSession {
+  pub mod_id: String,
+  pub mod_name: String,
+  pub session_id: String,
+  pub start_time: i64,
+  pub size_bytes: u64,
+  pub reason: LogPruneReason,
+}
+
+// Payload of the mod-logs-pruned event
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct LogRetentionReport {
+  pub pruned_sessions: Vec<PrunedSession>,
+  pub freed_bytes: u64,
+}
+
+// Payload of the mod-resource-usage event
+#[derive(Debug, Serialize, Clone)]
+pub struct ModResourceUsage {
+  pub mod_id: String,
+  pub session_id: String,
+  pub cpu_percent: f64, // Can go over 100 with multiple cores
+  pub memory_bytes: u64, // Resident memory
+  pub thread_count: u32,
+  pub uptime_secs: u64,
+}
+
+// Why two mods can't run at the same time
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchConflictReason {
+  SharedExecutableDirectory,
+  SharedModsFolder,
+}
+
+// A running mod that would conflict with the one being launched
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LaunchConflict {
+  pub conflicting_mod_id: String,
+  pub conflicting_mod_name: String,
+  pub reason: LaunchConflictReason,
+  pub shared_path: String,
+}
+
+// Errors returned when launching a mod
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LaunchError {
+  Conflict {
+    message: String,
+    conflict: LaunchConflict,
+  },
+  Failed {
+    message: String,
+  },
+}
+
+impl From<String> for LaunchError {
+  fn from(message: String) -> Self {
+    LaunchError::Failed { message }
+  }
+}
+
+impl std::fmt::Display for LaunchError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LaunchError::Conflict { message, .. } => write!(f, "{}", message),
+      LaunchError::Failed { message } => write!(f, "{}", message),
+    }
+  }
+}
+
+// Response structure for launching a mod
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModLaunchResult {
+  pub launched: bool,
+  pub queued_behind: Option<String>, // ID of the running mod this launch waits for
+}
+
+// A launched mod, persisted so it can be found again after Fridaylight restarts
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunningModRecord {
+  pub mod_id: String,
+  pub pid: u32,
+  pub executable_path: String,
+  pub process_path: Option<String>, // What the OS reports the process is running, differs from executable_path with runners
+  pub session: SessionInfo,
+}
+
+// Playtime statistics for a single mod, durations are in seconds
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModPlaytimeStats {
+  pub mod_id: String,
+  pub mod_name: String,
+  pub total_playtime: i64,
+  pub session_count: usize,
+  pub average_session_length: i64,
+  pub crash_count: usize,
+  pub last_played: Option<i64>,
+  pub recent_sessions: Vec<SessionInfo>, // Newest first
+}
+
+// Playtime statistics for the whole library, durations are in seconds
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LibraryPlaytimeStats {
+  pub total_playtime: i64,
+  pub session_count: usize,
+  pub average_session_length: i64,
+  pub crash_count: usize,
+  pub recent_sessions: Vec<SessionInfo>, // Newest first
+  pub mods: Vec<ModPlaytimeStats>, // Most played first
+}
+
+// Payload of the mod-crashed event
+#[derive(Debug, Serialize, Clone)]
+pub struct ModCrashed {
+  pub mod_id: String,
+  pub session_id: String,
+  pub exit_code: Option<i32>,
+  pub summary: String,
+  pub last_stderr: Vec<String>,
+  pub crash_files: Vec<String>, // Where the crash files were found
 }
 
 // Structure for the engine mods response
@@ -1009,8 +1408,12 @@ pub struct EngineModsResponse {
   pub mods: Vec<ModMetadataFile>,
 }
 
-// Create a state to manage our mods
-pub struct ModsState(pub Mutex<HashMap<String, ModInfo>>);
+// Create a state to manage our mods. The second set holds the mods that are
+// running their pre-launch hooks, it's only changed while holding the first lock
+pub struct ModsState(
+  pub Mutex<HashMap<String, ModInfo>>,
+  pub Mutex<HashSet<String>>,
+);
 
 // Event structures for download progress tracking
 #[derive(Clone, Serialize)]
@@ -1044,6 +1447,64 @@ pub struct DownloadError {
   pub mod_id: i64,
   pub name: String,
   pub error: String,
+  pub error_code: Option<String>, // Set for errors the frontend handles on its own, like checksum_mismatch
+}
+
+// Payload of the download-cancelled event
+#[derive(Clone, Serialize)]
+pub struct DownloadCancelled {
+  pub mod_id: i64,
+  pub name: String,
+}
+
+// Backend settings for downloads
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct DownloadSettings {
+  #[serde(default)]
+  pub keep_corrupted_downloads: bool, // Keep archives that failed the checksum check
+  #[serde(default)]
+  pub max_concurrent_downloads: Option<usize>,
+  #[serde(default)]
+  pub max_cache_size_mb: Option<u64>, // Size limit of the download cache
+  #[serde(default)]
+  pub keep_archives_after_install: bool, // Move installed archives to the archive library
+  #[serde(default)]
+  pub max_archive_library_size_mb: Option<u64>, // Size limit of the archive library
+}
+
+// Archive kept in the archive library after installing it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchivedDownload {
+  pub file_name: String,
+  pub path: String,
+  pub size: u64,
+  pub modified: i64, // Unix timestamp
+  pub name: Option<String>, // Name of the mod or engine it installed
+  pub install_path: Option<String>, // Folder it was extracted to
+}
+
+// Where a download is in the queue
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadJobState {
+  Queued,
+  Running,
+  Paused,
+  Completed,
+  Failed,
+  Cancelled,
+}
+
+// A download in the queue, as returned by list_downloads
+#[derive(Debug, Serialize, Clone)]
+pub struct DownloadJob {
+  pub id: i64, // The ID sent in the download events
+  pub name: String,
+  pub state: DownloadJobState,
+  pub queue_position: Option<usize>, // Only for queued and paused jobs, 0 is next
+  pub percentage: u8,
+  pub error: Option<String>,
+  pub queued_at: i64,
 }
 
 // Create a state to manage terminal output for each running mod
@@ -16,11 +16,75 @@ use zip::ZipArchive;
 use std::fs::File;
 use regex::Regex;
 
+// Mods that are launching or still being looked after once their process exited
+fn is_mod_busy(mod_id: &str) -> bool {
+  crate::app_handle
+    ::get_global_app_handle()
+    .and_then(|app_handle| {
+      app_handle
+        .try_state::<ModsState>()
+        .and_then(|mods_state| {
+          mods_state.1.lock().ok().map(|busy| busy.contains(mod_id))
+        })
+    })
+    .unwrap_or(false)
+}
+
+/// Keeps a mod from being launched again until it's dropped
+pub struct BusyModGuard {
+  mod_id: String,
+  // Mark the mod as not running once dropped
+  finish_running: bool,
+}
+
+impl BusyModGuard {
+  /// For a mod running its pre-launch hooks
+  pub fn launching(mods_state: &ModsState, mod_id: &str) -> Self {
+    mods_state.1.lock().unwrap().insert(mod_id.to_string());
+    BusyModGuard { mod_id: mod_id.to_string(), finish_running: false }
+  }
+
+  /// For a mod whose process is being monitored. It's only marked as not
+  /// running once the guard is dropped, after the post-exit hooks and the end
+  /// of its session, so nothing else starts in its install before that
+  pub fn running(mods_state: &ModsState, mod_id: &str) -> Self {
+    mods_state.1.lock().unwrap().insert(mod_id.to_string());
+    BusyModGuard { mod_id: mod_id.to_string(), finish_running: true }
+  }
+}
+
+impl Drop for BusyModGuard {
+  fn drop(&mut self) {
+    let app_handle = crate::app_handle::get_global_app_handle();
+    let mods_state = app_handle
+      .as_ref()
+      .and_then(|app_handle| app_handle.try_state::<ModsState>());
+    if let Some(mut busy) = mods_state.as_ref().and_then(|s| s.1.lock().ok()) {
+      busy.remove(&self.mod_id);
+    }
+    if self.finish_running {
+      set_mod_not_running(&self.mod_id);
+    }
+  }
+}
+
 // Function to update a mod's running state from any thread
 pub fn set_mod_not_running(mod_id: &str) {
+  // The process monitor does this itself once it's done with the mod
+  if is_mod_busy(mod_id) {
+    debug!("Mod {} is still finishing, not marking it as not running yet", mod_id);
+    return;
+  }
+
+  // Both stop_mod and the process monitor end up here, only report it once
+  let mut was_running = false;
+
+  crate::runningmods::forget_running_mod(mod_id);
+
   // First, update the global state if available
   if let Ok(mut mods) = GLOBAL_MODS_STATE.lock() {
     if let Some(mod_info) = mods.get_mut(mod_id) {
+      was_running |= mod_info.process_id.is_some();
       mod_info.process_id = None;
       mod_info.current_session_id = None;
       mod_info.session_start_time = None;
@@ -31,14 +95,12 @@ pub fn set_mod_not_running(mod_id: &str) {
     }
   }
 
-  // Add a log entry to note that the process has finished
-  crate::terminaloutput::add_log(mod_id, "[Process terminated]");
-
   // Also update the ModsState in the Tauri state manager
   if let Some(app_handle) = crate::app_handle::get_global_app_handle() {
     if let Some(mods_state) = app_handle.try_state::<ModsState>() {
       if let Ok(mut mods) = mods_state.0.lock() {
         if let Some(mod_info) = mods.get_mut(mod_id) {
+          was_running |= mod_info.process_id.is_some();
           mod_info.process_id = None;
           mod_info.current_session_id = None;
           mod_info.session_start_time = None;
@@ -51,6 +113,17 @@ pub fn set_mod_not_running(mod_id: &str) {
     }
   }
 
+  if !was_running {
+    debug!("Mod {} was already marked as not running", mod_id);
+    return;
+  }
+
+  // Add a log entry to note that the process has finished
+  crate::terminaloutput::add_log(mod_id, "[Process terminated]");
+
+  // Start anything that was waiting for this mod to exit
+  crate::launchconflicts::launch_queued(mod_id);
+
   // Emit an event to notify the UI about the process termination
   if
     let Err(e) = crate::app_handle::emit_event(
@@ -0,0 +1,345 @@
+use crate::downloadqueue::{ CancelToken, DOWNLOAD_CANCELLED, DOWNLOAD_PAUSED };
+use futures_util::StreamExt;
+use log::{ debug, info, warn };
+use md5::{ Digest, Md5 };
+use reqwest::header::{
+  CONTENT_RANGE,
+  CONTENT_TYPE,
+  ETAG,
+  HeaderMap,
+  IF_RANGE,
+  LAST_MODIFIED,
+  RANGE,
+};
+use reqwest::{ Response, StatusCode };
+use serde::{ Deserialize, Serialize };
+use std::fs::{ File, OpenOptions };
+use std::io::{ Read, Write };
+use std::path::{ Path, PathBuf };
+
+// How many bytes can arrive before the sidecar file is updated
+const SAVE_STATE_EVERY_BYTES: usize = 4 * 1024 * 1024;
+
+// Sidecar file next to a partial download, used to resume it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct PartialDownloadState {
+  url: String,
+  etag: Option<String>,
+  last_modified: Option<String>,
+  content_type: Option<String>,
+  total_bytes: usize, // 0 if the server didn't say
+  bytes_received: usize,
+}
+
+/// A download that was fully written to its partial file
+pub struct CompletedDownload {
+  pub path: PathBuf,
+  pub content_type: Option<String>,
+  pub total_bytes: usize,
+  pub md5: String, // Lowercase hex
+}
+
+/// Get the partial file for a download, the key has to stay the same
+/// between attempts for it to be resumed
+pub fn get_partial_path(dir: &Path, key: &str) -> PathBuf {
+  dir.join(format!("fridaylight-{}.part", key))
+}
+
+fn get_state_path(partial_path: &Path) -> PathBuf {
+  let mut file_name = partial_path.file_name().unwrap_or_default().to_os_string();
+  file_name.push(".json");
+  partial_path.with_file_name(file_name)
+}
+
+/// Download a file into its partial file, picking up where the last attempt
+/// left off if the server supports range requests. The partial file is kept
+/// if the download fails or is paused so the next attempt can resume it,
+/// but not if it's cancelled.
+/// on_started gets the total size, on_progress gets the bytes downloaded so far
+/// (including resumed ones) and the total size
+pub async fn download(
+  url: &str,
+  partial_path: &Path,
+  cancel_token: &CancelToken,
+  on_started: impl FnOnce(usize),
+  mut on_progress: impl FnMut(usize, usize)
+) -> Result<CompletedDownload, String> {
+  // Cancelling a paused download gets here to clean up
+  if cancel_token.is_cancelled() {
+    discard_partial(partial_path);
+    return Err(DOWNLOAD_CANCELLED.to_string());
+  }
+
+  let client = reqwest::Client::new();
+  let state_path = get_state_path(partial_path);
+  let previous_state = load_state(partial_path, url);
+
+  debug!("Sending HTTP request to {}", url);
+  let mut response = send_request(&client, url, previous_state.as_ref()).await?;
+
+  // Only keep what we have if the server sent exactly the rest of it
+  let mut resume_from = 0;
+  if let Some(state) = &previous_state {
+    let range_start = parse_content_range(response.headers()).map(|r| r.0);
+    if
+      response.status() == StatusCode::PARTIAL_CONTENT &&
+      range_start == Some(state.bytes_received)
+    {
+      info!("Resuming download of {} from byte {}", url, state.bytes_received);
+      resume_from = state.bytes_received;
+    } else {
+      info!(
+        "Server didn't resume download of {} (status {}), starting over",
+        url,
+        response.status()
+      );
+      // A 200 already has the whole file, anything else has to be asked again
+      if response.status() != StatusCode::OK {
+        response = send_request(&client, url, None).await?;
+      }
+    }
+  }
+
+  if !response.status().is_success() {
+    return Err(format!("Server returned error status: {}", response.status()));
+  }
+  if cancel_token.is_cancelled() {
+    discard_partial(partial_path);
+    return Err(DOWNLOAD_CANCELLED.to_string());
+  }
+
+  let total_bytes = if resume_from > 0 {
+    parse_content_range(response.headers())
+      .and_then(|r| r.1)
+      .unwrap_or_else(|| resume_from + (response.content_length().unwrap_or(0) as usize))
+  } else {
+    response.content_length().unwrap_or(0) as usize
+  };
+
+  // A resumed response might not say what the file is, the first one did
+  let resumed_state = previous_state.filter(|_| resume_from > 0);
+  let headers = response.headers();
+  let mut state = PartialDownloadState {
+    url: url.to_string(),
+    etag: header_string(headers, ETAG).or_else(||
+      resumed_state.as_ref().and_then(|s| s.etag.clone())
+    ),
+    last_modified: header_string(headers, LAST_MODIFIED).or_else(||
+      resumed_state.as_ref().and_then(|s| s.last_modified.clone())
+    ),
+    content_type: resumed_state
+      .as_ref()
+      .and_then(|s| s.content_type.clone())
+      .or_else(|| header_string(headers, CONTENT_TYPE)),
+    total_bytes,
+    bytes_received: resume_from,
+  };
+
+  let mut hasher = Md5::new();
+  let mut file = if resume_from > 0 {
+    hasher = hash_partial(partial_path).await?;
+    OpenOptions::new()
+      .append(true)
+      .open(partial_path)
+      .map_err(|e| format!("Failed to open file: {}", e))?
+  } else {
+    File::create(partial_path).map_err(|e| format!("Failed to create file: {}", e))?
+  };
+  save_state(&state_path, &state);
+
+  on_started(total_bytes);
+
+  let mut downloaded = resume_from;
+  let mut unsaved_bytes = 0;
+  on_progress(downloaded, total_bytes);
+
+  let mut stream = cancel_token.abortable_stream(response.bytes_stream());
+  while let Some(chunk_result) = stream.next().await {
+    let chunk = match chunk_result {
+      Ok(chunk) => chunk,
+      Err(e) => {
+        state.bytes_received = downloaded;
+        save_state(&state_path, &state);
+        return Err(format!("Failed to download chunk: {}", e));
+      }
+    };
+
+    if let Err(e) = file.write_all(&chunk) {
+      state.bytes_received = downloaded;
+      save_state(&state_path, &state);
+      return Err(format!("Failed to write to file: {}", e));
+    }
+    hasher.update(&chunk);
+    downloaded += chunk.len();
+
+    unsaved_bytes += chunk.len();
+    if unsaved_bytes >= SAVE_STATE_EVERY_BYTES {
+      state.bytes_received = downloaded;
+      save_state(&state_path, &state);
+      unsaved_bytes = 0;
+    }
+
+    on_progress(downloaded, total_bytes);
+  }
+
+  // A cancelled download isn't coming back, so there's no point keeping it
+  if cancel_token.is_cancelled() {
+    drop(file);
+    discard_partial(partial_path);
+    return Err(DOWNLOAD_CANCELLED.to_string());
+  }
+
+  state.bytes_received = downloaded;
+  save_state(&state_path, &state);
+  if cancel_token.is_paused() {
+    info!("Paused download of {} at byte {}", url, downloaded);
+    return Err(DOWNLOAD_PAUSED.to_string());
+  }
+  if total_bytes > 0 && downloaded < total_bytes {
+    return Err(
+      format!(
+        "Connection closed after {} of {} bytes",
+        downloaded,
+        total_bytes
+      )
+    );
+  }
+
+  Ok(CompletedDownload {
+    path: partial_path.to_path_buf(),
+    content_type: state.content_type,
+    total_bytes: downloaded,
+    md5: format!("{:x}", hasher.finalize()),
+  })
+}
+
+/// Move a completed download to where it should go, and forget its state
+pub fn finish(download: &CompletedDownload, target: &Path) -> Result<(), String> {
+  crate::downloadcache::move_file(&download.path, target)?;
+  remove_state(&download.path);
+  debug!("Moved download to {}", target.display());
+  Ok(())
+}
+
+/// Delete a completed download that isn't wanted, and its state
+pub fn discard(download: &CompletedDownload) {
+  discard_partial(&download.path);
+}
+
+fn discard_partial(partial_path: &Path) {
+  match std::fs::remove_file(partial_path) {
+    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+      warn!("Failed to delete {}: {}", partial_path.display(), e);
+    }
+    _ => {}
+  }
+  remove_state(partial_path);
+}
+
+fn remove_state(partial_path: &Path) {
+  let state_path = get_state_path(partial_path);
+  match std::fs::remove_file(&state_path) {
+    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+      warn!("Failed to delete {}: {}", state_path.display(), e);
+    }
+    _ => {}
+  }
+}
+
+// The state of an earlier attempt at the same URL, if there's anything to resume
+fn load_state(partial_path: &Path, url: &str) -> Option<PartialDownloadState> {
+  let bytes_on_disk = std::fs::metadata(partial_path).ok()?.len() as usize;
+  let file = File::open(get_state_path(partial_path)).ok()?;
+  let state = match serde_json::from_reader::<_, PartialDownloadState>(file) {
+    Ok(state) => state,
+    Err(e) => {
+      warn!("Failed to parse state of {}: {}", partial_path.display(), e);
+      return None;
+    }
+  };
+  if state.url != url || bytes_on_disk == 0 {
+    return None;
+  }
+
+  // Everything written to the file made it, even if the state wasn't saved after
+  Some(PartialDownloadState {
+    bytes_received: bytes_on_disk,
+    ..state
+  })
+}
+
+fn save_state(state_path: &Path, state: &PartialDownloadState) {
+  if let Err(e) = crate::utils::write_json_file(state_path, state) {
+    warn!("Failed to save download state: {}", e);
+  }
+}
+
+async fn send_request(
+  client: &reqwest::Client,
+  url: &str,
+  resume_state: Option<&PartialDownloadState>
+) -> Result<Response, String> {
+  let mut request = client.get(url);
+  if let Some(state) = resume_state {
+    request = request.header(RANGE, format!("bytes={}-", state.bytes_received));
+    // Get the whole file instead if it changed since, weak ETags aren't allowed here
+    let validator = state.etag
+      .as_ref()
+      .filter(|etag| !etag.starts_with("W/"))
+      .or(state.last_modified.as_ref());
+    if let Some(validator) = validator {
+      request = request.header(IF_RANGE, validator);
+    }
+  }
+
+  let response = request.send().await.map_err(|e| format!("Failed to download: {}", e))?;
+  debug!("Received response with status: {}", response.status());
+  Ok(response)
+}
+
+// Start and total size from a Content-Range like bytes 100-999/1000
+fn parse_content_range(headers: &HeaderMap) -> Option<(usize, Option<usize>)> {
+  let value = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+  let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+  let start = range.split_once('-')?.0.trim().parse().ok()?;
+  Some((start, total.trim().parse().ok()))
+}
+
+fn header_string(
+  headers: &HeaderMap,
+  name: reqwest::header::HeaderName
+) -> Option<String> {
+  headers
+    .get(name)?
+    .to_str()
+    .ok()
+    .map(|value| value.to_string())
+}
+
+// Hash what an earlier attempt already downloaded, reading a large partial
+// file blocks so it's kept off the async runtime
+async fn hash_partial(path: &Path) -> Result<Md5, String> {
+  let path = path.to_path_buf();
+  tauri::async_runtime
+    ::spawn_blocking(move || {
+      let mut hasher = Md5::new();
+      hash_file(&path, &mut hasher).map(|_| hasher)
+    }).await
+    .map_err(|e| format!("Failed to hash partial download: {}", e))?
+}
+
+fn hash_file(path: &Path, hasher: &mut Md5) -> Result<(), String> {
+  let mut file = File::open(path).map_err(|e|
+    format!("Failed to open {}: {}", path.display(), e)
+  )?;
+  let mut buffer = vec![0; 64 * 1024];
+  loop {
+    let read = file
+      .read(&mut buffer)
+      .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if read == 0 {
+      return Ok(());
+    }
+    hasher.update(&buffer[..read]);
+  }
+}
@@ -0,0 +1,718 @@
+use crate::models::StopMethod;
+use log::{ debug, info, warn };
+use std::collections::HashSet;
+use std::process::Command;
+use std::time::{ Duration, Instant };
+
+// How often to check whether a process has exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// How long to wait for a process to disappear after killing it
+const FORCE_KILL_TIMEOUT: Duration = Duration::from_secs(2);
+
+// A process running on the system
+#[derive(Debug, Clone)]
+pub struct ProcessEntry {
+  pub pid: u32,
+  pub parent_pid: u32,
+  pub group_id: Option<u32>, // Process group, not available on Windows
+  pub name: String,
+}
+
+impl std::fmt::Display for ProcessEntry {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} (PID {})", self.name, self.pid)
+  }
+}
+
+// What happened when stopping a mod and everything it spawned
+#[derive(Debug)]
+pub struct ProcessTreeStop {
+  pub method: StopMethod,
+  pub stopped_children: Vec<ProcessEntry>,
+  pub survivors: Vec<ProcessEntry>,
+}
+
+/// Put a mod's process in its own group so its helpers can be stopped with it.
+/// On Windows the process starts suspended, track_process_tree resumes it
+pub fn isolate_process_group(command: &mut Command) {
+  #[cfg(unix)]
+  {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+  }
+  #[cfg(target_os = "windows")]
+  {
+    // Nothing it spawns right away can escape the job this way
+    use std::os::windows::process::CommandExt;
+    command.creation_flags(winapi::um::winbase::CREATE_SUSPENDED);
+  }
+}
+
+/// Keep track of everything a launched mod spawns. On Windows the mod is put
+/// in a job object and resumed, everywhere else its process group already does this
+pub fn track_process_tree(child: &std::process::Child) {
+  #[cfg(target_os = "windows")]
+  {
+    if let Err(e) = assign_to_job(child) {
+      warn!("{}, falling back to parent PIDs", e);
+    }
+    // Even without a job, the mod has to run
+    if let Err(e) = resume_process(child.id()) {
+      warn!("{}", e);
+    }
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    let _ = child;
+  }
+}
+
+/// Stop tracking the processes of a mod that exited
+pub fn release_process_tree(pid: u32) {
+  #[cfg(target_os = "windows")]
+  {
+    JOBS.lock().unwrap().remove(&pid);
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    let _ = pid;
+  }
+}
+
+/// Format a command the way it would be typed in a shell
+pub fn format_command_line(command: &Command) -> String {
+  std::iter
+    ::once(command.get_program())
+    .chain(command.get_args())
+    .map(|part| {
+      let part = part.to_string_lossy();
+      if part.is_empty() || part.contains(char::is_whitespace) {
+        format!("\"{}\"", part.replace('"', "\\\""))
+      } else {
+        part.to_string()
+      }
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Find every process spawned by a process, including ones left in its group
+pub fn find_process_tree(root_pid: u32) -> Vec<ProcessEntry> {
+  // Everything in the job, even if its parent exited or its PID got reused
+  #[cfg(target_os = "windows")]
+  {
+    if let Some(job_pids) = job_process_ids(root_pid) {
+      return list_processes()
+        .into_iter()
+        .filter(|p| p.pid != root_pid && job_pids.contains(&p.pid))
+        .collect();
+    }
+  }
+
+  let processes = list_processes();
+  let mut found: HashSet<u32> = HashSet::new();
+  let mut queue = vec![root_pid];
+
+  // Processes in the root's group count even if they got reparented
+  for process in &processes {
+    if process.group_id == Some(root_pid) && process.pid != root_pid {
+      if found.insert(process.pid) {
+        queue.push(process.pid);
+      }
+    }
+  }
+
+  while let Some(parent) = queue.pop() {
+    for process in &processes {
+      if
+        process.parent_pid == parent &&
+        process.pid != root_pid &&
+        found.insert(process.pid)
+      {
+        queue.push(process.pid);
+      }
+    }
+  }
+
+  processes
+    .into_iter()
+    .filter(|p| found.contains(&p.pid))
+    .collect()
+}
+
+/// Stop a process and all of its descendants, asking first and killing after the grace period
+pub fn terminate_process_tree(
+  pid: u32,
+  grace_period: Duration
+) -> Result<ProcessTreeStop, String> {
+  let children = find_process_tree(pid);
+  let root_alive = is_process_alive(pid);
+  debug!("Process {} has {} child processes", pid, children.len());
+
+  if !root_alive && children.is_empty() {
+    debug!("Process {} already exited", pid);
+    return Ok(ProcessTreeStop {
+      method: StopMethod::AlreadyExited,
+      stopped_children: Vec::new(),
+      survivors: Vec::new(),
+    });
+  }
+
+  let child_pids = children
+    .iter()
+    .map(|p| p.pid)
+    .collect::<Vec<_>>();
+
+  if let Err(e) = request_tree_exit(pid, &child_pids) {
+    warn!("Failed to ask process {} to exit: {}", pid, e);
+  }
+
+  let mut method = if root_alive {
+    StopMethod::Graceful
+  } else {
+    StopMethod::AlreadyExited
+  };
+
+  if !wait_for_all(pid, &child_pids, grace_period) {
+    info!(
+      "Process {} or its children still running after {} ms, killing them",
+      pid,
+      grace_period.as_millis()
+    );
+
+    if is_process_alive(pid) {
+      method = StopMethod::Forced;
+    }
+    if let Err(e) = force_kill_tree(pid, &child_pids) {
+      warn!("Failed to kill process {}: {}", pid, e);
+    }
+
+    if is_process_alive(pid) && !wait_for_exit(pid, FORCE_KILL_TIMEOUT) {
+      return Err(
+        format!("Process {} is still running after being killed", pid)
+      );
+    }
+    wait_for_all(pid, &child_pids, FORCE_KILL_TIMEOUT);
+  }
+
+  let (survivors, stopped_children): (Vec<_>, Vec<_>) = children
+    .into_iter()
+    .partition(|p| is_process_alive(p.pid));
+  if method == StopMethod::AlreadyExited && !stopped_children.is_empty() {
+    method = StopMethod::ChildrenStopped;
+  }
+
+  info!(
+    "Stopped process {} ({:?}) with {} children, {} still running",
+    pid,
+    method,
+    stopped_children.len(),
+    survivors.len()
+  );
+
+  Ok(ProcessTreeStop {
+    method,
+    stopped_children,
+    survivors,
+  })
+}
+
+/// Wait until a process exits, returns false if it's still running after the timeout
+pub fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+  wait_for_all(pid, &[], timeout)
+}
+
+fn wait_for_all(pid: u32, child_pids: &[u32], timeout: Duration) -> bool {
+  let deadline = Instant::now() + timeout;
+  while
+    is_process_alive(pid) ||
+    child_pids.iter().any(|child| is_process_alive(*child))
+  {
+    if Instant::now() >= deadline {
+      return false;
+    }
+    std::thread::sleep(EXIT_POLL_INTERVAL);
+  }
+  true
+}
+
+/// Check if a process with the given PID is still running
+#[cfg(target_os = "linux")]
+pub fn is_process_alive(pid: u32) -> bool {
+  // Zombies are dead, they're just waiting for their parent to notice
+  match read_proc_stat(pid) {
+    Some((_, state, _, _)) => state != 'Z' && state != 'X',
+    None => false,
+  }
+}
+
+/// Check if a process with the given PID is still running
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn is_process_alive(pid: u32) -> bool {
+  // Signal 0 doesn't do anything, it only checks that the process exists
+  let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+  result == 0 ||
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Get the path of the executable a process is running
+#[cfg(target_os = "linux")]
+pub fn get_process_executable(pid: u32) -> Option<String> {
+  std::fs
+    ::read_link(format!("/proc/{}/exe", pid))
+    .ok()
+    .map(|path| path.to_string_lossy().to_string())
+}
+
+/// Get the path of the executable a process is running
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn get_process_executable(pid: u32) -> Option<String> {
+  let output = Command::new("ps")
+    .args(["-p", &pid.to_string(), "-o", "comm="])
+    .output()
+    .ok()?;
+  let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+  (!path.is_empty()).then_some(path)
+}
+
+// Parse /proc/<pid>/stat into (name, state, parent pid, group id)
+#[cfg(target_os = "linux")]
+fn read_proc_stat(pid: u32) -> Option<(String, char, u32, u32)> {
+  let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+
+  // The name is in parentheses and may contain spaces, so split around them
+  let name_start = stat.find('(')?;
+  let name_end = stat.rfind(')')?;
+  let name = stat[name_start + 1..name_end].to_string();
+  let mut fields = stat[name_end + 1..].split_whitespace();
+
+  let state = fields.next()?.chars().next()?;
+  let parent_pid = fields.next()?.parse().ok()?;
+  let group_id = fields.next()?.parse().ok()?;
+  Some((name, state, parent_pid, group_id))
+}
+
+#[cfg(target_os = "linux")]
+fn list_processes() -> Vec<ProcessEntry> {
+  let entries = match std::fs::read_dir("/proc") {
+    Ok(entries) => entries,
+    Err(e) => {
+      warn!("Failed to read /proc: {}", e);
+      return Vec::new();
+    }
+  };
+
+  entries
+    .filter_map(|e| e.ok())
+    .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+    .filter_map(|pid| {
+      let (name, state, parent_pid, group_id) = read_proc_stat(pid)?;
+      if state == 'Z' || state == 'X' {
+        return None;
+      }
+      Some(ProcessEntry {
+        pid,
+        parent_pid,
+        group_id: Some(group_id),
+        name,
+      })
+    })
+    .collect()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn list_processes() -> Vec<ProcessEntry> {
+  let output = match
+    Command::new("ps").args(["-A", "-o", "pid=,ppid=,pgid=,comm="]).output()
+  {
+    Ok(output) => output,
+    Err(e) => {
+      warn!("Failed to list processes: {}", e);
+      return Vec::new();
+    }
+  };
+
+  String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .filter_map(|line| {
+      let mut fields = line.split_whitespace();
+      let pid = fields.next()?.parse().ok()?;
+      let parent_pid = fields.next()?.parse().ok()?;
+      let group_id = fields.next()?.parse().ok()?;
+      let name = fields.collect::<Vec<_>>().join(" ");
+      Some(ProcessEntry {
+        pid,
+        parent_pid,
+        group_id: Some(group_id),
+        name,
+      })
+    })
+    .collect()
+}
+
+#[cfg(unix)]
+fn send_signal(pid: libc::pid_t, signal: libc::c_int) -> Result<(), String> {
+  if unsafe { libc::kill(pid, signal) } == 0 {
+    return Ok(());
+  }
+
+  let err = std::io::Error::last_os_error();
+  if err.raw_os_error() == Some(libc::ESRCH) {
+    // The process exited in the meantime
+    Ok(())
+  } else {
+    Err(format!("Failed to send signal {} to process {}: {}", signal, pid, err))
+  }
+}
+
+// Signal the whole process group, then anything that left it
+#[cfg(unix)]
+fn signal_tree(
+  pid: u32,
+  child_pids: &[u32],
+  signal: libc::c_int
+) -> Result<(), String> {
+  // A negative PID targets the process group the mod was launched in
+  let group_result = send_signal(-(pid as libc::pid_t), signal);
+  let root_result = send_signal(pid as libc::pid_t, signal);
+
+  for child in child_pids {
+    if let Err(e) = send_signal(*child as libc::pid_t, signal) {
+      warn!("{}", e);
+    }
+  }
+
+  group_result.or(root_result)
+}
+
+#[cfg(unix)]
+fn request_tree_exit(pid: u32, child_pids: &[u32]) -> Result<(), String> {
+  signal_tree(pid, child_pids, libc::SIGTERM)
+}
+
+#[cfg(unix)]
+fn force_kill_tree(pid: u32, child_pids: &[u32]) -> Result<(), String> {
+  signal_tree(pid, child_pids, libc::SIGKILL)
+}
+
+/// Check if a process with the given PID is still running
+#[cfg(target_os = "windows")]
+pub fn is_process_alive(pid: u32) -> bool {
+  use winapi::um::handleapi::CloseHandle;
+  use winapi::um::processthreadsapi::OpenProcess;
+  use winapi::um::synchapi::WaitForSingleObject;
+  use winapi::um::winbase::WAIT_TIMEOUT;
+  use winapi::um::winnt::SYNCHRONIZE;
+
+  unsafe {
+    let handle = OpenProcess(SYNCHRONIZE, 0, pid);
+    if handle.is_null() {
+      return false;
+    }
+    // The handle only gets signaled once the process has exited
+    let alive = WaitForSingleObject(handle, 0) == WAIT_TIMEOUT;
+    CloseHandle(handle);
+    alive
+  }
+}
+
+/// Get the path of the executable a process is running
+#[cfg(target_os = "windows")]
+pub fn get_process_executable(pid: u32) -> Option<String> {
+  use winapi::um::handleapi::CloseHandle;
+  use winapi::um::processthreadsapi::OpenProcess;
+  use winapi::um::winbase::QueryFullProcessImageNameW;
+  use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+  unsafe {
+    let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+    if handle.is_null() {
+      return None;
+    }
+
+    let mut buffer = [0u16; 1024];
+    let mut size = buffer.len() as u32;
+    let result = QueryFullProcessImageNameW(
+      handle,
+      0,
+      buffer.as_mut_ptr(),
+      &mut size
+    );
+    CloseHandle(handle);
+
+    (result != 0).then(|| String::from_utf16_lossy(&buffer[..size as usize]))
+  }
+}
+
+#[cfg(target_os = "windows")]
+fn list_processes() -> Vec<ProcessEntry> {
+  use winapi::um::handleapi::{ CloseHandle, INVALID_HANDLE_VALUE };
+  use winapi::um::tlhelp32::{
+    CreateToolhelp32Snapshot,
+    Process32FirstW,
+    Process32NextW,
+    PROCESSENTRY32W,
+    TH32CS_SNAPPROCESS,
+  };
+
+  let mut processes = Vec::new();
+  unsafe {
+    let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+    if snapshot == INVALID_HANDLE_VALUE {
+      warn!("Failed to list processes");
+      return processes;
+    }
+
+    let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+    entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+
+    let mut has_entry = Process32FirstW(snapshot, &mut entry) != 0;
+    while has_entry {
+      let name_len = entry.szExeFile
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(entry.szExeFile.len());
+      processes.push(ProcessEntry {
+        pid: entry.th32ProcessID,
+        parent_pid: entry.th32ParentProcessID,
+        group_id: None,
+        name: String::from_utf16_lossy(&entry.szExeFile[..name_len]),
+      });
+      has_entry = Process32NextW(snapshot, &mut entry) != 0;
+    }
+
+    CloseHandle(snapshot);
+  }
+  processes
+}
+
+#[cfg(target_os = "windows")]
+struct JobHandle(winapi::um::winnt::HANDLE);
+
+// Only used behind the JOBS lock
+#[cfg(target_os = "windows")]
+unsafe impl Send for JobHandle {}
+
+#[cfg(target_os = "windows")]
+impl Drop for JobHandle {
+  fn drop(&mut self) {
+    unsafe {
+      winapi::um::handleapi::CloseHandle(self.0);
+    }
+  }
+}
+
+#[cfg(target_os = "windows")]
+lazy_static::lazy_static! {
+  // Job objects of launched mods (root PID -> job), anything a mod spawns
+  // ends up in its job too
+  static ref JOBS: std::sync::Mutex<std::collections::HashMap<u32, JobHandle>> =
+    std::sync::Mutex::new(std::collections::HashMap::new());
+}
+
+#[cfg(target_os = "windows")]
+fn assign_to_job(child: &std::process::Child) -> Result<(), String> {
+  use std::os::windows::io::AsRawHandle;
+  use winapi::um::jobapi2::{ AssignProcessToJobObject, CreateJobObjectW };
+
+  // Not JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, mods keep running when
+  // Fridaylight closes so they can be re-attached after a restart
+  unsafe {
+    let job = CreateJobObjectW(std::ptr::null_mut(), std::ptr::null());
+    if job.is_null() {
+      return Err(
+        format!(
+          "Failed to create job object: {}",
+          std::io::Error::last_os_error()
+        )
+      );
+    }
+    let job = JobHandle(job);
+    if AssignProcessToJobObject(job.0, child.as_raw_handle() as _) == 0 {
+      return Err(
+        format!(
+          "Failed to add process {} to a job object: {}",
+          child.id(),
+          std::io::Error::last_os_error()
+        )
+      );
+    }
+    JOBS.lock().unwrap().insert(child.id(), job);
+  }
+  debug!("Added process {} to a job object", child.id());
+  Ok(())
+}
+
+// Resume the threads of a process that was started suspended
+#[cfg(target_os = "windows")]
+fn resume_process(pid: u32) -> Result<(), String> {
+  use winapi::um::handleapi::{ CloseHandle, INVALID_HANDLE_VALUE };
+  use winapi::um::processthreadsapi::{ OpenThread, ResumeThread };
+  use winapi::um::tlhelp32::{
+    CreateToolhelp32Snapshot,
+    Thread32First,
+    Thread32Next,
+    THREADENTRY32,
+    TH32CS_SNAPTHREAD,
+  };
+  use winapi::um::winnt::THREAD_SUSPEND_RESUME;
+
+  let mut resumed = 0;
+  unsafe {
+    let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+    if snapshot == INVALID_HANDLE_VALUE {
+      return Err(
+        format!(
+          "Failed to list the threads of process {}: {}",
+          pid,
+          std::io::Error::last_os_error()
+        )
+      );
+    }
+
+    let mut entry: THREADENTRY32 = std::mem::zeroed();
+    entry.dwSize = std::mem::size_of::<THREADENTRY32>() as u32;
+    let mut has_entry = Thread32First(snapshot, &mut entry) != 0;
+    while has_entry {
+      if entry.th32OwnerProcessID == pid {
+        let thread = OpenThread(THREAD_SUSPEND_RESUME, 0, entry.th32ThreadID);
+        if !thread.is_null() {
+          if ResumeThread(thread) != u32::MAX {
+            resumed += 1;
+          }
+          CloseHandle(thread);
+        }
+      }
+      has_entry = Thread32Next(snapshot, &mut entry) != 0;
+    }
+
+    CloseHandle(snapshot);
+  }
+
+  if resumed == 0 {
+    return Err(format!("Failed to resume process {}", pid));
+  }
+  debug!("Resumed {} threads of process {}", resumed, pid);
+  Ok(())
+}
+
+// Processes in the job of a launched mod, None if it doesn't have one
+#[cfg(target_os = "windows")]
+fn job_process_ids(root_pid: u32) -> Option<Vec<u32>> {
+  use winapi::um::jobapi2::QueryInformationJobObject;
+  use winapi::um::winnt::{
+    JobObjectBasicProcessIdList,
+    JOBOBJECT_BASIC_PROCESS_ID_LIST,
+  };
+
+  let jobs = JOBS.lock().unwrap();
+  let job = jobs.get(&root_pid)?;
+
+  // Room for the header and up to 1024 process IDs
+  let mut buffer = vec![0usize; 1026];
+  unsafe {
+    let list = buffer.as_mut_ptr() as *mut JOBOBJECT_BASIC_PROCESS_ID_LIST;
+    let result = QueryInformationJobObject(
+      job.0,
+      JobObjectBasicProcessIdList,
+      list as _,
+      (buffer.len() * std::mem::size_of::<usize>()) as u32,
+      std::ptr::null_mut()
+    );
+    if result == 0 {
+      warn!(
+        "Failed to list the processes of job {}: {}",
+        root_pid,
+        std::io::Error::last_os_error()
+      );
+      return None;
+    }
+
+    let count = (*list).NumberOfProcessIdsInList as usize;
+    let ids = std::ptr::addr_of!((*list).ProcessIdList) as *const usize;
+    Some(
+      std::slice
+        ::from_raw_parts(ids, count)
+        .iter()
+        .map(|id| *id as u32)
+        .collect()
+    )
+  }
+}
+
+// Kill everything in the job of a launched mod, false if it doesn't have one
+#[cfg(target_os = "windows")]
+fn terminate_job(root_pid: u32) -> Result<bool, String> {
+  use winapi::um::jobapi2::TerminateJobObject;
+
+  let jobs = JOBS.lock().unwrap();
+  let Some(job) = jobs.get(&root_pid) else {
+    return Ok(false);
+  };
+  if unsafe { TerminateJobObject(job.0, 1) } == 0 {
+    return Err(
+      format!(
+        "Failed to terminate job object: {}",
+        std::io::Error::last_os_error()
+      )
+    );
+  }
+  Ok(true)
+}
+
+#[cfg(target_os = "windows")]
+fn run_taskkill(pid: u32, tree: bool, force: bool) -> Result<(), String> {
+  let pid_str = pid.to_string();
+  let mut args = vec!["/PID", &pid_str];
+  if tree {
+    args.push("/T");
+  }
+  if force {
+    args.push("/F");
+  }
+
+  let output = Command::new("taskkill")
+    .args(&args)
+    .output()
+    .map_err(|e| format!("Failed to run taskkill: {}", e))?;
+
+  if output.status.success() {
+    Ok(())
+  } else {
+    Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+  }
+}
+
+#[cfg(target_os = "windows")]
+fn signal_tree(pid: u32, child_pids: &[u32], force: bool) -> Result<(), String> {
+  let root_result = run_taskkill(pid, true, force);
+
+  // Children of an already exited process aren't reached through /T
+  for child in child_pids {
+    if is_process_alive(*child) {
+      if let Err(e) = run_taskkill(*child, true, force) {
+        warn!("Failed to stop child process {}: {}", child, e);
+      }
+    }
+  }
+
+  root_result
+}
+
+#[cfg(target_os = "windows")]
+fn request_tree_exit(pid: u32, child_pids: &[u32]) -> Result<(), String> {
+  // Without /F taskkill asks the windows of the processes to close
+  signal_tree(pid, child_pids, false)
+}
+
+#[cfg(target_os = "windows")]
+fn force_kill_tree(pid: u32, child_pids: &[u32]) -> Result<(), String> {
+  match terminate_job(pid) {
+    Ok(true) => Ok(()),
+    Ok(false) => signal_tree(pid, child_pids, true),
+    Err(e) => {
+      warn!("{}, killing processes one by one", e);
+      signal_tree(pid, child_pids, true)
+    }
+  }
+}
@@ -0,0 +1,289 @@
+use crate::models::ModResourceUsage;
+use lazy_static::lazy_static;
+use log::{ debug, info, warn };
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{ Duration, Instant };
+
+// How often resource usage gets sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+// Usage summed over a mod's process and its children
+#[derive(Debug, Clone, Copy, Default)]
+struct ProcessUsage {
+  cpu_time: Duration,
+  memory_bytes: u64,
+  thread_count: u32,
+}
+
+// Running totals for a session (session_id -> stats)
+#[derive(Debug, Clone, Default)]
+struct SessionResourceStats {
+  peak_memory_bytes: u64,
+  cpu_percent_total: f64,
+  sample_count: u64,
+}
+
+lazy_static! {
+  static ref SESSION_STATS: Mutex<HashMap<String, SessionResourceStats>> =
+    Mutex::new(HashMap::new());
+}
+
+/// Sample a mod's resource usage until its process exits or the session ends
+pub fn start_monitoring(
+  mod_id: &str,
+  session_id: &str,
+  pid: u32,
+  session_start_time: i64
+) {
+  if !is_supported() {
+    debug!("Resource monitoring isn't supported on this platform");
+    return;
+  }
+
+  SESSION_STATS.lock()
+    .unwrap()
+    .insert(session_id.to_string(), SessionResourceStats::default());
+
+  let mod_id = mod_id.to_string();
+  let session_id = session_id.to_string();
+  std::thread::spawn(move || {
+    let mut last_sample: Option<(Instant, Duration)> = None;
+    info!("Monitoring resource usage of PID {} for mod {}", pid, mod_id);
+
+    loop {
+      std::thread::sleep(SAMPLE_INTERVAL);
+
+      // Stop once the session has ended
+      if !SESSION_STATS.lock().unwrap().contains_key(&session_id) {
+        break;
+      }
+      let usage = match read_tree_usage(pid) {
+        Some(usage) => usage,
+        None => {
+          break;
+        }
+      };
+
+      let now = Instant::now();
+      let cpu_percent = match last_sample {
+        Some((last_time, last_cpu)) => {
+          let elapsed = now.duration_since(last_time).as_secs_f64();
+          // Children exiting can make the total go down, don't report that as negative usage
+          let cpu = usage.cpu_time.saturating_sub(last_cpu).as_secs_f64();
+          if elapsed > 0.0 {
+            (cpu / elapsed) * 100.0
+          } else {
+            0.0
+          }
+        }
+        None => 0.0,
+      };
+      let first_sample = last_sample.is_none();
+      last_sample = Some((now, usage.cpu_time));
+
+      if let Some(stats) = SESSION_STATS.lock().unwrap().get_mut(&session_id) {
+        stats.peak_memory_bytes = stats.peak_memory_bytes.max(
+          usage.memory_bytes
+        );
+        // The first sample has nothing to compare CPU time against
+        if !first_sample {
+          stats.cpu_percent_total += cpu_percent;
+          stats.sample_count += 1;
+        }
+      }
+
+      let payload = ModResourceUsage {
+        mod_id: mod_id.clone(),
+        session_id: session_id.clone(),
+        cpu_percent,
+        memory_bytes: usage.memory_bytes,
+        thread_count: usage.thread_count,
+        uptime_secs: (chrono::Utc::now().timestamp() - session_start_time).max(
+          0
+        ) as u64,
+      };
+      if let Err(e) = crate::app_handle::emit_event("mod-resource-usage", payload) {
+        warn!("Failed to emit mod-resource-usage event: {}", e);
+      }
+    }
+
+    debug!("Stopped monitoring resource usage of PID {}", pid);
+  });
+}
+
+/// Stop monitoring a session, returns its peak memory and average CPU usage
+pub fn finish_session(session_id: &str) -> Option<(u64, f64)> {
+  let stats = SESSION_STATS.lock().unwrap().remove(session_id)?;
+  if stats.peak_memory_bytes == 0 {
+    return None;
+  }
+
+  let average_cpu = if stats.sample_count > 0 {
+    stats.cpu_percent_total / (stats.sample_count as f64)
+  } else {
+    0.0
+  };
+  Some((stats.peak_memory_bytes, average_cpu))
+}
+
+/// Whether resource usage can be sampled on this platform
+pub fn is_supported() -> bool {
+  cfg!(any(target_os = "linux", target_os = "windows"))
+}
+
+// Sum the usage of a process and everything it spawned
+#[cfg(target_os = "linux")]
+fn read_tree_usage(pid: u32) -> Option<ProcessUsage> {
+  let mut total = read_process_usage(pid)?;
+  for child in crate::processes::find_process_tree(pid) {
+    if let Some(usage) = read_process_usage(child.pid) {
+      total.cpu_time += usage.cpu_time;
+      total.memory_bytes += usage.memory_bytes;
+      total.thread_count += usage.thread_count;
+    }
+  }
+  Some(total)
+}
+
+// Sum the usage of a process and everything it spawned
+#[cfg(target_os = "windows")]
+fn read_tree_usage(pid: u32) -> Option<ProcessUsage> {
+  if !crate::processes::is_process_alive(pid) {
+    return None;
+  }
+
+  let thread_counts = read_thread_counts();
+  let usage_of = |pid: u32| {
+    read_process_usage(pid).map(|usage| ProcessUsage {
+      thread_count: thread_counts.get(&pid).copied().unwrap_or(0),
+      ..usage
+    })
+  };
+
+  let mut total = usage_of(pid)?;
+  for child in crate::processes::find_process_tree(pid) {
+    if let Some(usage) = usage_of(child.pid) {
+      total.cpu_time += usage.cpu_time;
+      total.memory_bytes += usage.memory_bytes;
+      total.thread_count += usage.thread_count;
+    }
+  }
+  Some(total)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn read_tree_usage(_pid: u32) -> Option<ProcessUsage> {
+  None
+}
+
+// CPU time and working set of a process, the thread count is filled in separately
+#[cfg(target_os = "windows")]
+fn read_process_usage(pid: u32) -> Option<ProcessUsage> {
+  use winapi::shared::minwindef::FILETIME;
+  use winapi::um::handleapi::CloseHandle;
+  use winapi::um::processthreadsapi::{ GetProcessTimes, OpenProcess };
+  use winapi::um::psapi::{ GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS };
+  use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+  // FILETIMEs count 100 nanosecond intervals
+  fn filetime_duration(time: &FILETIME) -> Duration {
+    let intervals =
+      ((time.dwHighDateTime as u64) << 32) | (time.dwLowDateTime as u64);
+    Duration::from_nanos(intervals * 100)
+  }
+
+  unsafe {
+    let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+    if handle.is_null() {
+      return None;
+    }
+
+    let mut creation_time: FILETIME = std::mem::zeroed();
+    let mut exit_time: FILETIME = std::mem::zeroed();
+    let mut kernel_time: FILETIME = std::mem::zeroed();
+    let mut user_time: FILETIME = std::mem::zeroed();
+    let times_result = GetProcessTimes(
+      handle,
+      &mut creation_time,
+      &mut exit_time,
+      &mut kernel_time,
+      &mut user_time
+    );
+
+    let mut counters: PROCESS_MEMORY_COUNTERS = std::mem::zeroed();
+    counters.cb = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    let memory_result = GetProcessMemoryInfo(handle, &mut counters, counters.cb);
+    CloseHandle(handle);
+
+    if times_result == 0 || memory_result == 0 {
+      return None;
+    }
+    Some(ProcessUsage {
+      cpu_time: filetime_duration(&kernel_time) + filetime_duration(&user_time),
+      memory_bytes: counters.WorkingSetSize as u64,
+      thread_count: 0,
+    })
+  }
+}
+
+// Thread count of every process, from a single snapshot
+#[cfg(target_os = "windows")]
+fn read_thread_counts() -> HashMap<u32, u32> {
+  use winapi::um::handleapi::{ CloseHandle, INVALID_HANDLE_VALUE };
+  use winapi::um::tlhelp32::{
+    CreateToolhelp32Snapshot,
+    Process32FirstW,
+    Process32NextW,
+    PROCESSENTRY32W,
+    TH32CS_SNAPPROCESS,
+  };
+
+  let mut counts = HashMap::new();
+  unsafe {
+    let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+    if snapshot == INVALID_HANDLE_VALUE {
+      return counts;
+    }
+
+    let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+    entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+    let mut has_entry = Process32FirstW(snapshot, &mut entry) != 0;
+    while has_entry {
+      counts.insert(entry.th32ProcessID, entry.cntThreads);
+      has_entry = Process32NextW(snapshot, &mut entry) != 0;
+    }
+
+    CloseHandle(snapshot);
+  }
+  counts
+}
+
+#[cfg(target_os = "linux")]
+fn read_process_usage(pid: u32) -> Option<ProcessUsage> {
+  let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+
+  // Skip past the name, it's in parentheses and may contain spaces
+  let fields = stat[stat.rfind(')')? + 1..]
+    .split_whitespace()
+    .collect::<Vec<_>>();
+  // Zombies have already exited
+  if fields.first() == Some(&"Z") {
+    return None;
+  }
+
+  // Fields are numbered from 1 in proc(5), and the name is field 2
+  let utime = fields.get(11)?.parse::<u64>().ok()?;
+  let stime = fields.get(12)?.parse::<u64>().ok()?;
+  let thread_count = fields.get(17)?.parse::<u32>().ok()?;
+  let rss_pages = fields.get(21)?.parse::<u64>().ok()?;
+
+  let ticks_per_second = (unsafe { libc::sysconf(libc::_SC_CLK_TCK) }).max(1) as u64;
+  let page_size = (unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).max(1) as u64;
+
+  Some(ProcessUsage {
+    cpu_time: Duration::from_millis(((utime + stime) * 1000) / ticks_per_second),
+    memory_bytes: rss_pages * page_size,
+    thread_count,
+  })
+}
@@ -0,0 +1,268 @@
+use crate::models::{ ModInfo, RunnerConfig, RunnerKind, RunnersConfig };
+use lazy_static::lazy_static;
+use log::{ debug, error, info, warn };
+use std::path::{ Path, PathBuf };
+use std::process::Command;
+use std::sync::Mutex;
+
+// File in the app data directory holding the configured runners
+const RUNNERS_FILE: &str = "runners.json";
+
+// Serializes reads and writes of the runners file
+lazy_static! {
+  static ref RUNNERS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn load_runners_config() -> RunnersConfig {
+  match crate::utils::get_app_data_file(RUNNERS_FILE) {
+    Ok(path) => crate::utils::read_json_file(&path),
+    Err(e) => {
+      error!("Failed to resolve runners file: {}", e);
+      RunnersConfig::default()
+    }
+  }
+}
+
+fn save_runners_config(config: &RunnersConfig) -> Result<(), String> {
+  let path = crate::utils::get_app_data_file(RUNNERS_FILE)?;
+  crate::utils::write_json_file(&path, config)
+}
+
+/// Get all configured runners along with the default one
+pub fn get_runners() -> RunnersConfig {
+  let _guard = RUNNERS_LOCK.lock().unwrap();
+  load_runners_config()
+}
+
+/// Add a new runner or update an existing one with the same ID
+pub fn save_runner(mut runner: RunnerConfig) -> Result<RunnerConfig, String> {
+  if !Path::new(&runner.path).exists() {
+    return Err(format!("Runner executable not found: {}", runner.path));
+  }
+
+  if runner.id.is_empty() {
+    runner.id = uuid::Uuid::new_v4().to_string();
+  }
+  if runner.name.trim().is_empty() {
+    runner.name = Path::new(&runner.path)
+      .file_name()
+      .unwrap_or_default()
+      .to_string_lossy()
+      .to_string();
+  }
+
+  let _guard = RUNNERS_LOCK.lock().unwrap();
+  let mut config = load_runners_config();
+  match config.runners.iter_mut().find(|r| r.id == runner.id) {
+    Some(existing) => {
+      *existing = runner.clone();
+    }
+    None => {
+      config.runners.push(runner.clone());
+    }
+  }
+
+  // The first runner becomes the default so Windows builds work out of the box
+  if config.default_runner_id.is_none() {
+    config.default_runner_id = Some(runner.id.clone());
+  }
+
+  save_runners_config(&config)?;
+  info!("Saved runner {} ({})", runner.name, runner.id);
+  Ok(runner)
+}
+
+/// Remove a runner, clearing it as the default if needed
+pub fn remove_runner(runner_id: &str) -> Result<(), String> {
+  let _guard = RUNNERS_LOCK.lock().unwrap();
+  let mut config = load_runners_config();
+  let count = config.runners.len();
+  config.runners.retain(|r| r.id != runner_id);
+
+  if config.runners.len() == count {
+    return Err(format!("Runner not found: {}", runner_id));
+  }
+  if config.default_runner_id.as_deref() == Some(runner_id) {
+    config.default_runner_id = None;
+  }
+
+  save_runners_config(&config)?;
+  info!("Removed runner {}", runner_id);
+  Ok(())
+}
+
+/// Set the runner used by mods that don't pick one themselves
+pub fn set_default_runner(runner_id: Option<String>) -> Result<(), String> {
+  let _guard = RUNNERS_LOCK.lock().unwrap();
+  let mut config = load_runners_config();
+
+  if let Some(id) = &runner_id {
+    if !config.runners.iter().any(|r| &r.id == id) {
+      return Err(format!("Runner not found: {}", id));
+    }
+  }
+
+  config.default_runner_id = runner_id;
+  save_runners_config(&config)
+}
+
+/// Get the runner that should wrap a mod's Windows executable
+pub fn get_runner_for_mod(mod_info: &ModInfo) -> Result<RunnerConfig, String> {
+  let config = get_runners();
+
+  // A runner picked for this mod wins over the default one
+  let runner_id = mod_info.runner_id
+    .clone()
+    .or(config.default_runner_id.clone())
+    .ok_or_else(|| {
+      format!(
+        "{} only has a Windows executable and no Wine/Proton runner is configured",
+        mod_info.name
+      )
+    })?;
+
+  config.runners
+    .into_iter()
+    .find(|r| r.id == runner_id)
+    .ok_or_else(|| format!("Runner not found: {}", runner_id))
+}
+
+/// Whether an executable has to go through a runner on this platform
+pub fn needs_runner(executable_path: &str) -> bool {
+  match crate::filesystem::detect_executable_kind(Path::new(executable_path)) {
+    Some(kind) => !crate::filesystem::is_native_executable(kind),
+    None => false,
+  }
+}
+
+/// Build the command that runs an executable through a runner
+pub fn build_runner_command(
+  runner: &RunnerConfig,
+  executable_path: &str
+) -> Result<Command, String> {
+  if !Path::new(&runner.path).exists() {
+    return Err(
+      format!("Runner {} not found at {}", runner.name, runner.path)
+    );
+  }
+
+  let mut command = Command::new(&runner.path);
+
+  match runner.kind {
+    RunnerKind::Wine => {
+      command.arg(executable_path);
+      if let Some(prefix) = &runner.prefix {
+        command.env("WINEPREFIX", prefix);
+      }
+    }
+    RunnerKind::Proton => {
+      command.arg("run").arg(executable_path);
+
+      // Proton refuses to start without a compat data path
+      let prefix = match &runner.prefix {
+        Some(prefix) => PathBuf::from(prefix),
+        None =>
+          crate::utils::get_app_data_file(
+            &format!("prefixes/{}", runner.id)
+          )?,
+      };
+      if let Err(e) = std::fs::create_dir_all(&prefix) {
+        return Err(
+          format!(
+            "Failed to create Proton prefix {}: {}",
+            prefix.display(),
+            e
+          )
+        );
+      }
+      command.env("STEAM_COMPAT_DATA_PATH", &prefix);
+
+      if let Some(steam_dir) = find_steam_directory() {
+        command.env("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam_dir);
+      } else {
+        warn!("Steam install not found, Proton may fail to start");
+      }
+    }
+  }
+
+  // Wine is very chatty on stderr, keep the terminal output readable
+  command.env("WINEDEBUG", "fixme-all");
+
+  if !runner.dll_overrides.is_empty() {
+    let mut overrides = runner.dll_overrides
+      .iter()
+      .map(|(dll, mode)| format!("{}={}", dll, mode))
+      .collect::<Vec<_>>();
+    overrides.sort();
+    command.env("WINEDLLOVERRIDES", overrides.join(";"));
+  }
+
+  // User-provided variables go last so they can override ours
+  command.envs(&runner.env);
+
+  debug!(
+    "Built {:?} runner command for {}: {:?}",
+    runner.kind,
+    executable_path,
+    command
+  );
+  Ok(command)
+}
+
+/// Look for Wine and Proton installs on this machine
+pub fn detect_runners() -> Vec<RunnerConfig> {
+  let mut runners = Vec::new();
+
+  // Wine from PATH
+  if let Some(paths) = std::env::var_os("PATH") {
+    for dir in std::env::split_paths(&paths) {
+      let wine = dir.join("wine");
+      if wine.is_file() {
+        debug!("Found Wine at {}", wine.display());
+        runners.push(detected_runner("Wine", RunnerKind::Wine, &wine));
+        break;
+      }
+    }
+  }
+
+  // Proton from Steam's common folder and custom compatibility tools
+  if let Some(steam_dir) = find_steam_directory() {
+    for folder in ["steamapps/common", "compatibilitytools.d"] {
+      let search_dir = steam_dir.join(folder);
+      if let Ok(entries) = std::fs::read_dir(&search_dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+          let proton = entry.path().join("proton");
+          if proton.is_file() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            debug!("Found Proton at {}", proton.display());
+            runners.push(detected_runner(&name, RunnerKind::Proton, &proton));
+          }
+        }
+      }
+    }
+  }
+
+  info!("Detected {} runners", runners.len());
+  runners
+}
+
+fn detected_runner(name: &str, kind: RunnerKind, path: &Path) -> RunnerConfig {
+  RunnerConfig {
+    id: String::new(), // Assigned when the runner gets saved
+    name: name.to_string(),
+    kind,
+    path: path.to_string_lossy().to_string(),
+    prefix: None,
+    env: Default::default(),
+    dll_overrides: Default::default(),
+  }
+}
+
+// Find the Steam install, Proton needs it to run
+fn find_steam_directory() -> Option<PathBuf> {
+  let home = PathBuf::from(std::env::var_os("HOME")?);
+  [".steam/root", ".steam/steam", ".local/share/Steam"]
+    .iter()
+    .map(|dir| home.join(dir))
+    .find(|dir| dir.is_dir())
+}
@@ -0,0 +1,171 @@
+use crate::models::{ ModInfo, RunningModRecord };
+use lazy_static::lazy_static;
+use log::{ debug, error, info, warn };
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// File in the app data directory holding the mods that are currently running
+const RUNNING_MODS_FILE: &str = "running_mods.json";
+// How often to check on mods we can't wait on directly
+const REATTACHED_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+lazy_static! {
+  // Serializes reads and writes of the running mods file
+  static ref RUNNING_MODS_LOCK: Mutex<()> = Mutex::new(());
+  // Mods that were still running after a restart (mod_id -> record),
+  // applied to the mod states once the frontend syncs them
+  static ref REATTACHED_MODS: Mutex<HashMap<String, RunningModRecord>> =
+    Mutex::new(HashMap::new());
+}
+
+fn load_running_mods() -> Vec<RunningModRecord> {
+  match crate::utils::get_app_data_file(RUNNING_MODS_FILE) {
+    Ok(path) => crate::utils::read_json_file(&path),
+    Err(e) => {
+      error!("Failed to resolve running mods file: {}", e);
+      Vec::new()
+    }
+  }
+}
+
+fn save_running_mods(records: &[RunningModRecord]) {
+  let result = crate::utils
+    ::get_app_data_file(RUNNING_MODS_FILE)
+    .and_then(|path| crate::utils::write_json_file(&path, &records));
+  if let Err(e) = result {
+    error!("Failed to save running mods: {}", e);
+  }
+}
+
+/// Remember a launched mod so it can be found again after a restart
+pub fn remember_running_mod(record: RunningModRecord) {
+  let _guard = RUNNING_MODS_LOCK.lock().unwrap();
+  let mut records = load_running_mods();
+  records.retain(|r| r.mod_id != record.mod_id);
+  debug!("Remembering running mod {} (PID {})", record.mod_id, record.pid);
+  records.push(record);
+  save_running_mods(&records);
+}
+
+/// Forget a mod that stopped running
+pub fn forget_running_mod(mod_id: &str) {
+  REATTACHED_MODS.lock().unwrap().remove(mod_id);
+
+  let _guard = RUNNING_MODS_LOCK.lock().unwrap();
+  let mut records = load_running_mods();
+  let count = records.len();
+  records.retain(|r| r.mod_id != mod_id);
+  if records.len() != count {
+    debug!("Forgetting running mod {}", mod_id);
+    save_running_mods(&records);
+  }
+}
+
+/// Mark a mod as running if it was re-attached after a restart
+pub fn apply_running_state(mod_info: &mut ModInfo) {
+  if mod_info.process_id.is_some() {
+    return;
+  }
+
+  if let Some(record) = REATTACHED_MODS.lock().unwrap().get(&mod_info.id) {
+    mod_info.process_id = Some(record.pid);
+    mod_info.current_session_id = Some(record.session.session_id.clone());
+    mod_info.session_start_time = Some(record.session.start_time);
+  }
+}
+
+// Check that a PID still belongs to the game we launched, and not some other process
+fn is_same_process(record: &RunningModRecord) -> bool {
+  if !crate::processes::is_process_alive(record.pid) {
+    return false;
+  }
+
+  let current_path = crate::processes::get_process_executable(record.pid);
+  match (&record.process_path, current_path) {
+    (Some(expected), Some(current)) => expected == &current,
+    // Couldn't read it at launch, fall back to the mod's executable
+    (None, Some(current)) => {
+      std::path::Path::new(&current) ==
+        std::path::Path::new(&record.executable_path)
+    }
+    (_, None) => false,
+  }
+}
+
+/// Go through the mods that were running when Fridaylight closed,
+/// re-attach the ones still running and close the sessions of the others
+pub fn reconcile_on_startup() {
+  let records = {
+    let _guard = RUNNING_MODS_LOCK.lock().unwrap();
+    load_running_mods()
+  };
+  if records.is_empty() {
+    return;
+  }
+  info!("Reconciling {} mods that were running before the restart", records.len());
+
+  let mut still_running = Vec::new();
+  for record in records {
+    if is_same_process(&record) {
+      info!(
+        "Mod {} is still running with PID {}, re-attaching",
+        record.session.mod_name,
+        record.pid
+      );
+      reattach(&record);
+      still_running.push(record);
+    } else {
+      info!(
+        "Mod {} (PID {}) is no longer running, closing its session",
+        record.session.mod_name,
+        record.pid
+      );
+      crate::terminaloutput::close_stale_session(record.session);
+    }
+  }
+
+  let _guard = RUNNING_MODS_LOCK.lock().unwrap();
+  save_running_mods(&still_running);
+}
+
+fn reattach(record: &RunningModRecord) {
+  let mod_id = record.mod_id.clone();
+  let session_id = record.session.session_id.clone();
+  let pid = record.pid;
+
+  crate::terminaloutput::resume_session(record.session.clone());
+  crate::terminaloutput::add_log(
+    &mod_id,
+    &format!(
+      "Re-attached to PID {} after Fridaylight restarted, output is no longer captured",
+      pid
+    )
+  );
+  REATTACHED_MODS.lock().unwrap().insert(mod_id.clone(), record.clone());
+  crate::resourcemonitor::start_monitoring(
+    &mod_id,
+    &session_id,
+    pid,
+    record.session.start_time
+  );
+
+  // The process isn't our child anymore, so poll it instead of waiting on it
+  std::thread::spawn(move || {
+    while crate::processes::is_process_alive(pid) {
+      std::thread::sleep(REATTACHED_POLL_INTERVAL);
+    }
+
+    let exit_message = "Process exited with unknown status";
+    info!("{} (PID {})", exit_message, pid);
+    crate::terminaloutput::add_log(&mod_id, exit_message);
+
+    match crate::terminaloutput::get_active_session(&mod_id) {
+      Some(active) if active == session_id => {
+        crate::terminaloutput::end_session(&mod_id, &session_id, None);
+      }
+      _ => warn!("Session {} is no longer active", session_id),
+    }
+    crate::modutils::set_mod_not_running(&mod_id);
+  });
+}
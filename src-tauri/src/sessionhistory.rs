@@ -0,0 +1,281 @@
+use crate::models::{ LibraryPlaytimeStats, ModPlaytimeStats, SessionInfo };
+use lazy_static::lazy_static;
+use log::{ debug, error, info, warn };
+use serde::{ Deserialize, Serialize };
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// File in the app data directory holding every finished session
+const SESSION_HISTORY_FILE: &str = "session_history.json";
+// Sessions kept in full, older ones only count towards their mod's totals
+const MAX_HISTORY_SESSIONS: usize = 2000;
+
+// Totals of the sessions that got compacted out of the history
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct CompactedPlaytime {
+  mod_name: String,
+  total_playtime: i64,
+  session_count: usize,
+  crash_count: usize,
+  last_played: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct SessionHistory {
+  sessions: Vec<SessionInfo>,
+  #[serde(default)]
+  compacted: HashMap<String, CompactedPlaytime>, // mod_id -> totals
+}
+
+lazy_static! {
+  // The history, loaded from disk the first time it's needed. Also
+  // serializes writes of the history file
+  static ref SESSION_HISTORY: Mutex<Option<SessionHistory>> = Mutex::new(None);
+}
+
+fn load_history() -> SessionHistory {
+  let path = match crate::utils::get_app_data_file(SESSION_HISTORY_FILE) {
+    Ok(path) => path,
+    Err(e) => {
+      error!("Failed to resolve session history file: {}", e);
+      return SessionHistory::default();
+    }
+  };
+
+  if path.exists() {
+    let content = match std::fs::read_to_string(&path) {
+      Ok(content) => content,
+      Err(e) => {
+        error!("Failed to read session history: {}", e);
+        return SessionHistory::default();
+      }
+    };
+    if let Ok(history) = serde_json::from_str::<SessionHistory>(&content) {
+      return history;
+    }
+    // The history used to be a plain list of sessions
+    match serde_json::from_str::<Vec<SessionInfo>>(&content) {
+      Ok(sessions) => {
+        let mut history = SessionHistory {
+          sessions,
+          ..Default::default()
+        };
+        compact(&mut history);
+        save_history(&history);
+        return history;
+      }
+      Err(e) => {
+        // Keep the broken file, it has compacted totals and sessions of mods
+        // that don't save their output, none of which can be rebuilt
+        let backup_path = path.with_extension(
+          format!("json.corrupted-{}", chrono::Utc::now().timestamp())
+        );
+        warn!(
+          "Failed to parse session history, moving it to {} and starting over: {}",
+          backup_path.display(),
+          e
+        );
+        if let Err(e) = std::fs::copy(&path, &backup_path) {
+          error!("Failed to back up corrupted session history: {}", e);
+        }
+      }
+    }
+  }
+
+  // First run with history, start from the sessions already saved to disk
+  let mut sessions = crate::terminaloutput::load_all_session_infos_from_disk();
+  sessions.retain(|s| s.end_time.is_some());
+  info!("Created session history from {} saved sessions", sessions.len());
+  let mut history = SessionHistory {
+    sessions,
+    ..Default::default()
+  };
+  compact(&mut history);
+  save_history(&history);
+  history
+}
+
+fn save_history(history: &SessionHistory) {
+  match crate::utils::get_app_data_file(SESSION_HISTORY_FILE) {
+    Ok(path) => {
+      if let Err(e) = crate::utils::write_json_file(&path, history) {
+        error!("Failed to save session history: {}", e);
+      }
+    }
+    Err(e) => error!("Failed to resolve session history file: {}", e),
+  }
+}
+
+// Fold the oldest sessions into their mod's totals once there are too many
+fn compact(history: &mut SessionHistory) {
+  if history.sessions.len() <= MAX_HISTORY_SESSIONS {
+    return;
+  }
+
+  history.sessions.sort_by_key(|s| std::cmp::Reverse(s.start_time));
+  let old_sessions = history.sessions.split_off(MAX_HISTORY_SESSIONS);
+  debug!("Compacting {} old sessions in history", old_sessions.len());
+  for session in old_sessions {
+    let totals = history.compacted.entry(session.mod_id.clone()).or_default();
+    if totals.mod_name.is_empty() {
+      totals.mod_name = session.mod_name.clone();
+    }
+    totals.total_playtime += session_length(&session);
+    totals.session_count += 1;
+    if session.crashed {
+      totals.crash_count += 1;
+    }
+    totals.last_played = totals.last_played.max(Some(session.start_time));
+  }
+}
+
+// Run something on the history, loading it first if needed
+fn with_history<R, F: FnOnce(&mut SessionHistory) -> R>(f: F) -> R {
+  let mut history = SESSION_HISTORY.lock().unwrap();
+  let history = history.get_or_insert_with(load_history);
+  f(history)
+}
+
+/// Add a finished session to the history
+pub fn record_session(session_info: SessionInfo) {
+  with_history(|history| {
+    history.sessions.retain(|s| s.session_id != session_info.session_id);
+    debug!(
+      "Recording session {} of mod {} in history",
+      session_info.session_id,
+      session_info.mod_name
+    );
+    history.sessions.push(session_info);
+    compact(history);
+    save_history(history);
+  });
+}
+
+/// Find a finished session in the history
+pub fn get_session(session_id: &str) -> Option<SessionInfo> {
+  with_history(|history| {
+    history.sessions
+      .iter()
+      .find(|s| s.session_id == session_id)
+      .cloned()
+  })
+}
+
+fn session_length(session: &SessionInfo) -> i64 {
+  session.end_time
+    .map(|end| (end - session.start_time).max(0))
+    .unwrap_or(0)
+}
+
+// Build the stats of a mod from its sessions and the ones compacted away
+fn mod_stats(
+  mod_id: &str,
+  mut sessions: Vec<SessionInfo>,
+  compacted: Option<&CompactedPlaytime>,
+  recent_count: usize
+) -> ModPlaytimeStats {
+  sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+  let compacted = compacted.cloned().unwrap_or_default();
+
+  let total_playtime =
+    sessions.iter().map(session_length).sum::<i64>() + compacted.total_playtime;
+  let session_count = sessions.len() + compacted.session_count;
+
+  ModPlaytimeStats {
+    mod_id: mod_id.to_string(),
+    mod_name: sessions
+      .first()
+      .map(|s| s.mod_name.clone())
+      .unwrap_or(compacted.mod_name),
+    total_playtime,
+    session_count,
+    average_session_length: if session_count > 0 {
+      total_playtime / (session_count as i64)
+    } else {
+      0
+    },
+    crash_count: sessions
+      .iter()
+      .filter(|s| s.crashed)
+      .count() + compacted.crash_count,
+    last_played: sessions
+      .first()
+      .map(|s| s.start_time)
+      .or(compacted.last_played),
+    recent_sessions: sessions.into_iter().take(recent_count).collect(),
+  }
+}
+
+/// Get the playtime stats of a mod
+pub fn get_mod_stats(mod_id: &str, recent_count: usize) -> ModPlaytimeStats {
+  let (sessions, compacted) = with_history(|history| {
+    (
+      history.sessions
+        .iter()
+        .filter(|s| s.mod_id == mod_id)
+        .cloned()
+        .collect(),
+      history.compacted.get(mod_id).cloned(),
+    )
+  });
+  mod_stats(mod_id, sessions, compacted.as_ref(), recent_count)
+}
+
+/// Get the playtime stats of the whole library
+pub fn get_library_stats(recent_count: usize) -> LibraryPlaytimeStats {
+  let SessionHistory { sessions: mut history, compacted } = with_history(|history|
+    history.clone()
+  );
+  history.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+
+  let total_playtime =
+    history.iter().map(session_length).sum::<i64>() +
+    compacted
+      .values()
+      .map(|c| c.total_playtime)
+      .sum::<i64>();
+  let session_count =
+    history.len() +
+    compacted
+      .values()
+      .map(|c| c.session_count)
+      .sum::<usize>();
+  let crash_count =
+    history
+      .iter()
+      .filter(|s| s.crashed)
+      .count() +
+    compacted
+      .values()
+      .map(|c| c.crash_count)
+      .sum::<usize>();
+  let recent_sessions = history.iter().take(recent_count).cloned().collect();
+
+  let mut sessions_by_mod: HashMap<String, Vec<SessionInfo>> = HashMap::new();
+  for mod_id in compacted.keys() {
+    sessions_by_mod.entry(mod_id.clone()).or_default();
+  }
+  for session in history {
+    sessions_by_mod.entry(session.mod_id.clone()).or_default().push(session);
+  }
+  let mut mods = sessions_by_mod
+    .into_iter()
+    .map(|(mod_id, sessions)| {
+      mod_stats(&mod_id, sessions, compacted.get(&mod_id), recent_count)
+    })
+    .collect::<Vec<_>>();
+  mods.sort_by(|a, b| b.total_playtime.cmp(&a.total_playtime));
+
+  LibraryPlaytimeStats {
+    total_playtime,
+    session_count,
+    average_session_length: if session_count > 0 {
+      total_playtime / (session_count as i64)
+    } else {
+      0
+    },
+    crash_count,
+    recent_sessions,
+    mods,
+  }
+}
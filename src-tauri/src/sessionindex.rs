@@ -0,0 +1,414 @@
+use crate::models::{ SessionInfo, SessionPage };
+use lazy_static::lazy_static;
+use log::{ debug, error, info, warn };
+use std::fs::File;
+use std::path::{ Path, PathBuf };
+use std::sync::Mutex;
+
+// Index of every saved session, kept at the root of the logs folder
+const SESSION_INDEX_FILE: &str = "session_index.json";
+// Per-mod indexes used before there was a single one
+const LEGACY_MOD_INDEX_FILE: &str = "sessions.json";
+// Marks a logs folder where session folders are already keyed by mod ID
+const MOD_ID_LAYOUT_MARKER: &str = ".mod_id_layout";
+// Where session folders that can't be moved to the new layout are set aside
+const UNMIGRATED_FOLDER: &str = ".unmigrated";
+
+// Page size when the caller doesn't set one
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+lazy_static! {
+  // Every saved session newest first, loaded from disk the first time it's
+  // needed. Also serializes writes of the index file
+  static ref SESSION_INDEX: Mutex<Option<Vec<SessionInfo>>> = Mutex::new(None);
+}
+
+/// Get every saved session of a mod, newest first
+pub fn list_sessions(mod_id: &str) -> Vec<SessionInfo> {
+  with_index(|sessions| {
+    sessions
+      .iter()
+      .filter(|s| s.mod_id == mod_id)
+      .cloned()
+      .collect()
+  })
+}
+
+/// Get a page of saved sessions, of one mod or of every mod, newest first
+pub fn query_sessions(
+  mod_id: Option<&str>,
+  offset: usize,
+  limit: Option<usize>
+) -> SessionPage {
+  let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+  with_index(|sessions| {
+    let matching = sessions
+      .iter()
+      .filter(|s| mod_id.is_none_or(|mod_id| s.mod_id == mod_id))
+      .collect::<Vec<_>>();
+    let total = matching.len();
+    let page = matching
+      .into_iter()
+      .skip(offset)
+      .take(limit)
+      .cloned()
+      .collect::<Vec<_>>();
+
+    SessionPage {
+      has_more: offset + page.len() < total,
+      sessions: page,
+      total,
+      offset,
+    }
+  })
+}
+
+/// Find a saved session of a mod
+pub fn find_session(mod_id: &str, session_id: &str) -> Option<SessionInfo> {
+  with_index(|sessions| {
+    sessions
+      .iter()
+      .find(|s| s.mod_id == mod_id && s.session_id == session_id)
+      .cloned()
+  })
+}
+
+/// Get every saved session of every mod
+pub fn list_all_sessions() -> Vec<SessionInfo> {
+  with_index(|sessions| sessions.clone())
+}
+
+/// Add a session to the index, or update it if it's already there
+pub fn upsert_session(session_info: &SessionInfo) {
+  with_index(|sessions| {
+    let existing = sessions
+      .iter_mut()
+      .find(|s| s.session_id == session_info.session_id);
+    match existing {
+      Some(existing) => {
+        *existing = session_info.clone();
+      }
+      None => {
+        sessions.push(session_info.clone());
+        sort_newest_first(sessions);
+      }
+    }
+    save_index(sessions);
+  });
+}
+
+/// Remove a session from the index, returns how many sessions its mod has left
+pub fn remove_session(mod_id: &str, session_id: &str) -> usize {
+  with_index(|sessions| {
+    sessions.retain(|s| !(s.mod_id == mod_id && s.session_id == session_id));
+    save_index(sessions);
+    sessions
+      .iter()
+      .filter(|s| s.mod_id == mod_id)
+      .count()
+  })
+}
+
+/// Remove every session of a mod from the index
+pub fn remove_mod_sessions(mod_id: &str) {
+  with_index(|sessions| {
+    sessions.retain(|s| s.mod_id != mod_id);
+    save_index(sessions);
+  });
+}
+
+/// Forget every session, for when the whole logs folder was deleted
+pub fn clear_index() {
+  *SESSION_INDEX.lock().unwrap() = Some(Vec::new());
+}
+
+/// Throw away the index and build it again from the session folders on disk
+pub fn rebuild_index() -> usize {
+  let mut index = SESSION_INDEX.lock().unwrap();
+  let sessions = scan_logs_dir();
+  save_index(&sessions);
+  let count = sessions.len();
+  *index = Some(sessions);
+  count
+}
+
+// Run something on the index, loading it first if needed
+fn with_index<R, F: FnOnce(&mut Vec<SessionInfo>) -> R>(f: F) -> R {
+  let mut index = SESSION_INDEX.lock().unwrap();
+  let sessions = index.get_or_insert_with(load_index);
+  f(sessions)
+}
+
+// Read the index file, rebuilding it from the session folders if it's
+// missing or corrupted
+fn load_index() -> Vec<SessionInfo> {
+  let index_path = match get_index_path() {
+    Some(index_path) => index_path,
+    None => {
+      return Vec::new();
+    }
+  };
+
+  if let Ok(file) = File::open(&index_path) {
+    match serde_json::from_reader::<_, Vec<SessionInfo>>(file) {
+      Ok(mut sessions) => {
+        sort_newest_first(&mut sessions);
+        return sessions;
+      }
+      Err(e) => {
+        warn!("Session index {} is corrupted, rebuilding it: {}", index_path.display(), e);
+      }
+    }
+  }
+
+  let sessions = scan_logs_dir();
+  save_index(&sessions);
+  sessions
+}
+
+fn save_index(sessions: &[SessionInfo]) {
+  let index_path = match get_index_path() {
+    Some(index_path) => index_path,
+    None => {
+      return;
+    }
+  };
+  if let Err(e) = crate::utils::write_json_file(&index_path, &sessions) {
+    error!("Failed to save session index {}: {}", index_path.display(), e);
+  }
+}
+
+fn get_index_path() -> Option<PathBuf> {
+  match crate::terminaloutput::LOGS_DIR.as_ref() {
+    Ok(path_buf) => Some(path_buf.join(SESSION_INDEX_FILE)),
+    Err(e) => {
+      error!("Failed to resolve LOGS_DIR: {}", e);
+      None
+    }
+  }
+}
+
+fn sort_newest_first(sessions: &mut [SessionInfo]) {
+  sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+}
+
+// Every session found in the mod folders of the logs folder
+fn scan_logs_dir() -> Vec<SessionInfo> {
+  let logs_dir = match crate::terminaloutput::LOGS_DIR.as_ref() {
+    Ok(path_buf) => path_buf,
+    Err(e) => {
+      error!("Failed to resolve LOGS_DIR: {}", e);
+      return Vec::new();
+    }
+  };
+
+  let mut sessions = Vec::new();
+  if let Ok(entries) = std::fs::read_dir(logs_dir) {
+    for entry in entries.filter_map(|e| e.ok()) {
+      if !entry.path().is_dir() {
+        continue;
+      }
+      sessions.extend(
+        scan_session_folders(&entry.path())
+          .into_iter()
+          .map(|(_, session_info)| session_info)
+      );
+      // The single index replaces the per-mod ones
+      let _ = std::fs::remove_file(entry.path().join(LEGACY_MOD_INDEX_FILE));
+    }
+  }
+  sort_newest_first(&mut sessions);
+  info!("Rebuilt session index with {} sessions", sessions.len());
+  sessions
+}
+
+// Session folders in a folder along with their info
+fn scan_session_folders(dir: &Path) -> Vec<(PathBuf, SessionInfo)> {
+  let mut sessions = Vec::new();
+  if let Ok(entries) = std::fs::read_dir(dir) {
+    for entry in entries.filter_map(|e| e.ok()) {
+      let session_info_path = entry.path().join("session_info.json");
+      if let Ok(file) = File::open(&session_info_path) {
+        match serde_json::from_reader::<_, SessionInfo>(file) {
+          Ok(session_info) => sessions.push((entry.path(), session_info)),
+          Err(e) => {
+            warn!("Failed to parse {}: {}", session_info_path.display(), e);
+          }
+        }
+      }
+    }
+  }
+  sessions
+}
+
+/// Move session folders from the old layout, where they were grouped by mod name,
+/// into folders named after the mod ID. Runs on every start until nothing
+/// failed to move, folders that can't be moved at all are set aside
+pub fn migrate_name_based_folders() {
+  let logs_dir = match crate::terminaloutput::LOGS_DIR.as_ref() {
+    Ok(path_buf) => path_buf.clone(),
+    Err(e) => {
+      error!("Failed to resolve LOGS_DIR: {}", e);
+      return;
+    }
+  };
+  let marker_path = logs_dir.join(MOD_ID_LAYOUT_MARKER);
+  if !logs_dir.is_dir() || marker_path.exists() {
+    return;
+  }
+
+  info!("Moving session logs into folders named after mod IDs");
+  let mut index = SESSION_INDEX.lock().unwrap();
+  let mut moved_count = 0;
+  let mut set_aside_count = 0;
+  let mut failed_count = 0;
+
+  for folder in list_folders(&logs_dir) {
+    if folder.file_name() == Some(UNMIGRATED_FOLDER.as_ref()) {
+      continue;
+    }
+
+    for session_dir in list_folders(&folder) {
+      let session_info_path = session_dir.join("session_info.json");
+      let session_info = File::open(&session_info_path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| {
+          serde_json::from_reader::<_, SessionInfo>(file).map_err(|e| e.to_string())
+        });
+      let session_info = match session_info {
+        Ok(session_info) => session_info,
+        Err(e) => {
+          warn!("Failed to read {}: {}", session_info_path.display(), e);
+          match set_aside(&logs_dir, &folder, &session_dir) {
+            Ok(_) => {
+              set_aside_count += 1;
+            }
+            Err(_) => {
+              failed_count += 1;
+            }
+          }
+          continue;
+        }
+      };
+
+      let mod_dir = match crate::terminaloutput::get_mod_logs_dir(&session_info.mod_id) {
+        Some(mod_dir) => mod_dir,
+        None => {
+          failed_count += 1;
+          continue;
+        }
+      };
+      if mod_dir == folder {
+        continue;
+      }
+
+      // Folder names come from the session, so this is a copy of the same one
+      let target = mod_dir.join(session_dir.file_name().unwrap_or_default());
+      if target.exists() {
+        warn!(
+          "Not moving {}, {} already exists",
+          session_dir.display(),
+          target.display()
+        );
+        match set_aside(&logs_dir, &folder, &session_dir) {
+          Ok(_) => {
+            set_aside_count += 1;
+          }
+          Err(_) => {
+            failed_count += 1;
+          }
+        }
+        continue;
+      }
+      let result = std::fs
+        ::create_dir_all(&mod_dir)
+        .and_then(|_| std::fs::rename(&session_dir, &target));
+      match result {
+        Ok(_) => {
+          debug!("Moved {} to {}", session_dir.display(), target.display());
+          moved_count += 1;
+        }
+        Err(e) => {
+          error!("Failed to move {}: {}", session_dir.display(), e);
+          failed_count += 1;
+        }
+      }
+    }
+
+    // Remove the old folder once everything in it was moved
+    if let Ok(mut entries) = std::fs::read_dir(&folder) {
+      if entries.next().is_none() {
+        if let Err(e) = std::fs::remove_dir(&folder) {
+          warn!("Failed to remove {}: {}", folder.display(), e);
+        }
+      }
+    }
+  }
+
+  info!(
+    "Moved {} session folders, set {} aside in {}",
+    moved_count,
+    set_aside_count,
+    logs_dir.join(UNMIGRATED_FOLDER).display()
+  );
+  // An index written by an earlier run still points at the old folders
+  if moved_count > 0 || set_aside_count > 0 {
+    let sessions = scan_logs_dir();
+    save_index(&sessions);
+    *index = Some(sessions);
+  }
+  // Try the ones that failed again next time
+  if failed_count > 0 {
+    warn!("Failed to move {} session folders, will retry on next start", failed_count);
+    return;
+  }
+  if let Err(e) = std::fs::write(&marker_path, "") {
+    error!("Failed to write {}: {}", marker_path.display(), e);
+  }
+}
+
+fn list_folders(dir: &Path) -> Vec<PathBuf> {
+  std::fs
+    ::read_dir(dir)
+    .map(|entries| {
+      entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+// Move a session folder that can't be migrated out of the way, it keeps the
+// name of the folder it was in so it can still be found
+fn set_aside(
+  logs_dir: &Path,
+  folder: &Path,
+  session_dir: &Path
+) -> Result<PathBuf, String> {
+  let target = logs_dir
+    .join(UNMIGRATED_FOLDER)
+    .join(folder.file_name().unwrap_or_default())
+    .join(session_dir.file_name().unwrap_or_default());
+  let result = if target.exists() {
+    Err(format!("{} already exists", target.display()))
+  } else {
+    std::fs
+      ::create_dir_all(target.parent().unwrap_or(logs_dir))
+      .and_then(|_| std::fs::rename(session_dir, &target))
+      .map_err(|e| e.to_string())
+  };
+
+  match result {
+    Ok(_) => {
+      warn!("Set {} aside in {}", session_dir.display(), target.display());
+      Ok(target)
+    }
+    Err(e) => {
+      error!("Failed to set {} aside: {}", session_dir.display(), e);
+      Err(e)
+    }
+  }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{ HashMap, VecDeque };
 use std::sync::{ Arc, Mutex };
 use std::fs::{ File, OpenOptions };
 use std::io::{ BufWriter, Write, BufReader, BufRead };
@@ -7,17 +7,25 @@ use lazy_static::lazy_static;
 use log::{ debug, info, error, warn };
 use chrono::Utc;
 use tauri::Manager;
-use crate::models::SessionInfo;
+use crate::models::{
+  LogRecord,
+  LogStream,
+  SessionInfo,
+  SessionLogs,
+  SessionLogsSince,
+};
 use uuid;
 
 // Stores logs for each mod session
 // mod_id -> session_id -> logs
 pub type SessionLogStore = Arc<
-  Mutex<HashMap<String, HashMap<String, Vec<String>>>>
+  Mutex<HashMap<String, HashMap<String, VecDeque<LogRecord>>>>
 >;
 
 // Config
 const MAX_LOGS_IN_MEMORY: usize = 500;
+// Lines kept for mods that don't save their output, unless the policy says otherwise
+pub const DEFAULT_MAX_UNSAVED_LOGS_IN_MEMORY: usize = 5000;
 lazy_static! {
   static ref APP_HANDLE: tauri::AppHandle = crate::app_handle
     ::get_global_app_handle()
@@ -47,10 +55,45 @@ lazy_static! {
   pub static ref ACTIVE_SESSIONS: Arc<Mutex<HashMap<String, String>>> = Arc::new(Mutex::new(HashMap::new()));
 }
 
-/// Sanitize mod name for use in filesystem paths
-fn sanitize_mod_name_for_filesystem(mod_name: &str) -> String {
+// How many logs of each session were moved to disk or dropped from memory,
+// so offsets keep counting from the start of the session
+// session_id -> count
+lazy_static! {
+  static ref EVICTED_LOG_COUNTS: Mutex<HashMap<String, usize>> = Mutex::new(
+    HashMap::new()
+  );
+}
+
+// How many logs of each session were dropped because the mod doesn't save
+// its output and the in-memory buffer was full
+// session_id -> count
+lazy_static! {
+  static ref TRUNCATED_LOG_COUNTS: Mutex<HashMap<String, usize>> = Mutex::new(
+    HashMap::new()
+  );
+}
+
+// Whether each session that hasn't ended yet saves its output, read once when
+// it starts so adding a line doesn't have to look the mod up
+// session_id -> saves output
+lazy_static! {
+  static ref SESSION_SAVES_OUTPUT: Mutex<HashMap<String, bool>> = Mutex::new(
+    HashMap::new()
+  );
+}
+
+// Info of the sessions that haven't ended yet, kept even when nothing is saved to disk
+// session_id -> session info
+lazy_static! {
+  static ref SESSION_INFOS: Mutex<HashMap<String, SessionInfo>> = Mutex::new(
+    HashMap::new()
+  );
+}
+
+/// Sanitize a mod ID for use in filesystem paths
+fn sanitize_for_filesystem(mod_id: &str) -> String {
   let invalid_chars = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
-  let mut sanitized = mod_name
+  let mut sanitized = mod_id
     .chars()
     .map(|c| if invalid_chars.contains(&c) || c.is_control() { '_' } else { c })
     .collect::<String>();
@@ -68,6 +111,17 @@ fn sanitize_mod_name_for_filesystem(mod_name: &str) -> String {
   sanitized
 }
 
+/// Get the folder all sessions of a mod are saved in
+pub fn get_mod_logs_dir(mod_id: &str) -> Option<PathBuf> {
+  match &*LOGS_DIR {
+    Ok(path_buf) => Some(path_buf.join(sanitize_for_filesystem(mod_id))),
+    Err(e) => {
+      error!("Failed to resolve LOGS_DIR: {}", e);
+      None
+    }
+  }
+}
+
 /// Generate a date-based session folder name
 fn generate_session_folder_name(session_id: &str, start_time: i64) -> String {
   let datetime = chrono::DateTime::<Utc>
@@ -109,16 +163,25 @@ fn get_mod_save_terminal_output_setting(mod_id: &str) -> bool {
   false
 }
 
+// Whether a session saves its output, falls back to the mod's setting for
+// sessions that aren't running
+fn session_saves_output(mod_id: &str, session_id: &str) -> bool {
+  if let Some(saves_output) = SESSION_SAVES_OUTPUT.lock().unwrap().get(session_id) {
+    return *saves_output;
+  }
+  get_mod_save_terminal_output_setting(mod_id)
+}
+
 /// Ensure the logs directory exists for a specific mod session
 fn ensure_session_logs_dir(
-  mod_name: &str,
+  mod_id: &str,
   session_id: &str,
   start_time: i64
 ) -> std::io::Result<()> {
-  let sanitized_name = sanitize_mod_name_for_filesystem(mod_name);
+  let sanitized_id = sanitize_for_filesystem(mod_id);
   let session_folder = generate_session_folder_name(session_id, start_time);
   let logs_path = match &*LOGS_DIR {
-    Ok(path_buf) => path_buf.join(&sanitized_name).join(&session_folder),
+    Ok(path_buf) => path_buf.join(&sanitized_id).join(&session_folder),
     Err(e) => {
       error!("Failed to resolve LOGS_DIR: {}", e);
       return Err(
@@ -134,11 +197,11 @@ fn ensure_session_logs_dir(
 
 /// Create a log file path for a specific mod session
 fn get_session_log_file_path(
-  mod_name: &str,
+  mod_id: &str,
   session_id: &str,
   start_time: i64
 ) -> String {
-  let sanitized_name = sanitize_mod_name_for_filesystem(mod_name);
+  let sanitized_id = sanitize_for_filesystem(mod_id);
   let session_folder = generate_session_folder_name(session_id, start_time);
   let logs_dir = match &*LOGS_DIR {
     Ok(path_buf) => path_buf.display().to_string(),
@@ -147,16 +210,21 @@ fn get_session_log_file_path(
       String::new()
     }
   };
-  format!("{}/{}/{}/output.log", logs_dir, sanitized_name, session_folder)
+  format!("{}/{}/{}/output.log", logs_dir, sanitized_id, session_folder)
+}
+
+/// Structured records of a session are saved as JSON lines next to output.log
+fn get_session_records_file_path(log_file_path: &str) -> PathBuf {
+  Path::new(log_file_path).with_file_name("output.jsonl")
 }
 
 /// Create a session info file path for a specific mod session
 fn get_session_info_file_path(
-  mod_name: &str,
+  mod_id: &str,
   session_id: &str,
   start_time: i64
 ) -> String {
-  let sanitized_name = sanitize_mod_name_for_filesystem(mod_name);
+  let sanitized_id = sanitize_for_filesystem(mod_id);
   let session_folder = generate_session_folder_name(session_id, start_time);
   let logs_dir = match &*LOGS_DIR {
     Ok(path_buf) => path_buf.display().to_string(),
@@ -168,7 +236,7 @@ fn get_session_info_file_path(
   format!(
     "{}/{}/{}/session_info.json",
     logs_dir,
-    sanitized_name,
+    sanitized_id,
     session_folder
   )
 }
@@ -193,6 +261,12 @@ pub fn start_session(mod_id: &str, mod_name: &str) -> String {
     start_time,
     end_time: None,
     exit_code: None,
+    command_line: None,
+    crashed: false,
+    crash_summary: None,
+    crash_files: Vec::new(),
+    peak_memory_bytes: None,
+    average_cpu_percent: None,
   };
 
   // Check if this mod wants to save terminal output
@@ -200,34 +274,29 @@ pub fn start_session(mod_id: &str, mod_name: &str) -> String {
 
   // Only create session directory and save session info if saving is enabled
   if should_save_output {
-    if let Err(e) = ensure_session_logs_dir(mod_name, &session_id, start_time) {
+    if let Err(e) = ensure_session_logs_dir(mod_id, &session_id, start_time) {
       error!("Failed to create session logs directory: {}", e);
     } else {
       // Save session info file
-      let session_info_path = get_session_info_file_path(
-        mod_name,
-        &session_id,
-        start_time
-      );
-      if let Ok(file) = File::create(&session_info_path) {
-        if let Err(e) = serde_json::to_writer_pretty(file, &session_info) {
-          error!("Failed to write session info file: {}", e);
-        } else {
-          debug!("Created session info file: {}", session_info_path);
-        }
-      }
+      write_session_info_file(&session_info);
+      debug!("Created session info file for session {}", session_id);
     }
   } else {
     debug!("Skipping session directory creation for mod {} - save_terminal_output is disabled", mod_name);
   }
 
+  SESSION_INFOS.lock().unwrap().insert(session_id.clone(), session_info);
+  SESSION_SAVES_OUTPUT.lock()
+    .unwrap()
+    .insert(session_id.clone(), should_save_output);
+
   // Initialize session logs in memory
   {
     let mut logs = SESSION_LOGS.lock().unwrap();
     logs
       .entry(mod_id.to_string())
       .or_insert_with(HashMap::new)
-      .insert(session_id.clone(), Vec::new());
+      .insert(session_id.clone(), VecDeque::new());
   }
 
   // Track active session
@@ -247,18 +316,35 @@ pub fn end_session(mod_id: &str, session_id: &str, exit_code: Option<i32>) {
     mod_id,
     exit_code
   );
+  finish_session(mod_id, session_id, exit_code, true);
+}
 
-  // Remove from active sessions
+/// End the session of a launch that never got its process started, it
+/// doesn't count as playtime
+pub fn abort_session(mod_id: &str, session_id: &str) {
+  info!("Aborting session {} for mod {}", session_id, mod_id);
+  finish_session(mod_id, session_id, None, false);
+}
+
+fn finish_session(
+  mod_id: &str,
+  session_id: &str,
+  exit_code: Option<i32>,
+  record_playtime: bool
+) {
+  // Remove from active sessions, unless the mod was launched again since
   {
     let mut active_sessions = ACTIVE_SESSIONS.lock().unwrap();
-    active_sessions.remove(mod_id);
+    if active_sessions.get(mod_id).map(|s| s.as_str()) == Some(session_id) {
+      active_sessions.remove(mod_id);
+    }
   }
 
   // Save any remaining in-memory logs to disk if mod wants to save output
-  if get_mod_save_terminal_output_setting(mod_id) {
+  if session_saves_output(mod_id, session_id) {
     let mut logs = SESSION_LOGS.lock().unwrap();
     if let Some(mod_sessions) = logs.get_mut(mod_id) {
-      if let Some(session_logs) = mod_sessions.get(session_id) {
+      if let Some(session_logs) = mod_sessions.get_mut(session_id) {
         if !session_logs.is_empty() {
           // Save logs to disk if there are any
           if !session_logs.is_empty() {
@@ -266,7 +352,7 @@ pub fn end_session(mod_id: &str, session_id: &str, exit_code: Option<i32>) {
               let Err(e) = save_session_logs_to_disk(
                 mod_id,
                 session_id,
-                session_logs
+                session_logs.make_contiguous()
               )
             {
               error!("Failed to save session logs to disk: {}", e);
@@ -285,28 +371,183 @@ pub fn end_session(mod_id: &str, session_id: &str, exit_code: Option<i32>) {
     }
   }
 
-  // Update session info file with end time and exit code (only if saving is enabled)
-  if get_mod_save_terminal_output_setting(mod_id) {
-    if
-      let Ok(mut session_info) = load_session_info_from_disk(mod_id, session_id)
-    {
-      session_info.end_time = Some(Utc::now().timestamp());
-      session_info.exit_code = exit_code;
+  EVICTED_LOG_COUNTS.lock().unwrap().remove(session_id);
+  TRUNCATED_LOG_COUNTS.lock().unwrap().remove(session_id);
+
+  // Update session info file with end time, exit code and resource usage
+  let resource_usage = crate::resourcemonitor::finish_session(session_id);
+  update_session_info(mod_id, session_id, |session_info| {
+    session_info.end_time = Some(Utc::now().timestamp());
+    session_info.exit_code = exit_code;
+    if let Some((peak_memory, average_cpu)) = resource_usage {
+      session_info.peak_memory_bytes = Some(peak_memory);
+      session_info.average_cpu_percent = Some(average_cpu);
+    }
+  });
 
-      let session_info_path = get_session_info_file_path(
-        &session_info.mod_name,
-        session_id,
-        session_info.start_time
-      );
-      if let Ok(file) = File::create(&session_info_path) {
-        if let Err(e) = serde_json::to_writer_pretty(file, &session_info) {
-          error!("Failed to update session info file: {}", e);
+  // Keep the finished session in the history used for playtime stats
+  let session_info = SESSION_INFOS.lock().unwrap().remove(session_id);
+  match session_info {
+    Some(session_info) if record_playtime => {
+      crate::sessionhistory::record_session(session_info)
+    }
+    Some(_) => debug!("Not recording aborted session {} in history", session_id),
+    None => warn!("No session info in memory for session {}", session_id),
+  }
+  SESSION_SAVES_OUTPUT.lock().unwrap().remove(session_id);
+
+  crate::logretention::enforce_policy();
+}
+
+/// Update a session's info, the file on disk is only written if saving is enabled
+pub fn update_session_info<F: FnOnce(&mut SessionInfo)>(
+  mod_id: &str,
+  session_id: &str,
+  update: F
+) {
+  let should_save_output = session_saves_output(mod_id, session_id);
+
+  // Sessions that are still running have their info in memory
+  let updated = {
+    let mut session_infos = SESSION_INFOS.lock().unwrap();
+    match session_infos.get_mut(session_id) {
+      Some(session_info) => {
+        update(session_info);
+        Some(session_info.clone())
+      }
+      None if should_save_output => {
+        match load_session_info_from_disk(mod_id, session_id) {
+          Ok(mut session_info) => {
+            update(&mut session_info);
+            Some(session_info)
+          }
+          Err(e) => {
+            warn!(
+              "Could not load session info for session {}: {}",
+              session_id,
+              e
+            );
+            None
+          }
         }
       }
+      None => None,
+    }
+  };
+
+  if let Some(session_info) = updated {
+    if should_save_output {
+      write_session_info_file(&session_info);
     }
   }
 }
 
+/// Get the info of a session that hasn't ended yet
+pub fn get_session_info(session_id: &str) -> Option<SessionInfo> {
+  SESSION_INFOS.lock().unwrap().get(session_id).cloned()
+}
+
+/// Find a session's info wherever it is, in memory, on disk or in the history
+pub fn find_session_info(mod_id: &str, session_id: &str) -> Option<SessionInfo> {
+  get_session_info(session_id)
+    .or_else(|| load_session_info_from_disk(mod_id, session_id).ok())
+    .or_else(|| crate::sessionhistory::get_session(session_id))
+    .filter(|session_info| session_info.mod_id == mod_id)
+}
+
+/// Make a session from before a restart active again
+pub fn resume_session(session_info: SessionInfo) {
+  let mod_id = session_info.mod_id.clone();
+  let session_id = session_info.session_id.clone();
+  info!("Resuming session {} for mod {}", session_id, mod_id);
+
+  SESSION_LOGS.lock()
+    .unwrap()
+    .entry(mod_id.clone())
+    .or_insert_with(HashMap::new)
+    .entry(session_id.clone())
+    .or_insert_with(VecDeque::new);
+  // The mods aren't loaded yet, but only sessions that save their output have an info file
+  let saves_output = Path::new(
+    &get_session_info_file_path(&mod_id, &session_id, session_info.start_time)
+  ).exists();
+  SESSION_SAVES_OUTPUT.lock().unwrap().insert(session_id.clone(), saves_output);
+
+  ACTIVE_SESSIONS.lock().unwrap().insert(mod_id, session_id.clone());
+  SESSION_INFOS.lock().unwrap().insert(session_id, session_info);
+}
+
+/// Close a session whose process went away while Fridaylight wasn't running
+pub fn close_stale_session(mut session_info: SessionInfo) {
+  // The last write to the log is the best guess for when the mod stopped
+  let log_path = get_session_log_file_path(
+    &session_info.mod_id,
+    &session_info.session_id,
+    session_info.start_time
+  );
+  let last_log_time = std::fs
+    ::metadata(&log_path)
+    .and_then(|m| m.modified())
+    .ok()
+    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+    .map(|d| d.as_secs() as i64);
+
+  session_info.end_time = Some(
+    last_log_time.unwrap_or(session_info.start_time).max(session_info.start_time)
+  );
+  session_info.exit_code = None;
+  info!(
+    "Closing stale session {} for mod {}",
+    session_info.session_id,
+    session_info.mod_name
+  );
+
+  // Only sessions that saved their output have an info file to update
+  let session_info_path = get_session_info_file_path(
+    &session_info.mod_id,
+    &session_info.session_id,
+    session_info.start_time
+  );
+  if Path::new(&session_info_path).exists() {
+    write_session_info_file(&session_info);
+  }
+
+  crate::sessionhistory::record_session(session_info);
+}
+
+fn write_session_info_file(session_info: &SessionInfo) {
+  let session_info_path = get_session_info_file_path(
+    &session_info.mod_id,
+    &session_info.session_id,
+    session_info.start_time
+  );
+  if let Ok(file) = File::create(&session_info_path) {
+    if let Err(e) = serde_json::to_writer_pretty(file, session_info) {
+      error!("Failed to update session info file: {}", e);
+    }
+  }
+  crate::sessionindex::upsert_session(session_info);
+}
+
+/// Load the info of every session saved to disk
+pub fn load_all_session_infos_from_disk() -> Vec<SessionInfo> {
+  crate::sessionindex::list_all_sessions()
+}
+
+/// Get the folder a session's files are saved in, if it has one
+pub fn get_session_dir(mod_id: &str, session_id: &str) -> Option<PathBuf> {
+  let session_info = load_session_info_from_disk(mod_id, session_id).ok()?;
+  get_session_dir_for(&session_info)
+}
+
+/// Get the folder a session's files are saved in from its info, if it has one
+pub fn get_session_dir_for(session_info: &SessionInfo) -> Option<PathBuf> {
+  let session_dir = get_mod_logs_dir(&session_info.mod_id)?.join(
+    generate_session_folder_name(&session_info.session_id, session_info.start_time)
+  );
+  session_dir.is_dir().then_some(session_dir)
+}
+
 /// Get the active session ID for a mod
 pub fn get_active_session(mod_id: &str) -> Option<String> {
   let active_sessions = ACTIVE_SESSIONS.lock().unwrap();
@@ -318,52 +559,28 @@ fn load_session_info_from_disk(
   mod_id: &str,
   session_id: &str
 ) -> Result<SessionInfo, Box<dyn std::error::Error>> {
-  // We need to find the session info file, but we need the mod name for the path
-  // Try to find it by searching in the logs directory
-  let logs_dir = match &*LOGS_DIR {
-    Ok(path_buf) => path_buf,
-    Err(e) => {
-      return Err(format!("Failed to resolve LOGS_DIR: {}", e).into());
-    }
-  };
+  crate::sessionindex
+    ::find_session(mod_id, session_id)
+    .ok_or_else(|| "Session info not found".into())
+}
 
-  // Search for session info file in all mod directories
-  if let Ok(entries) = std::fs::read_dir(logs_dir) {
-    for entry in entries.filter_map(|e| e.ok()) {
-      if entry.path().is_dir() {
-        // Look through all date-based session folders in this mod directory
-        if let Ok(session_entries) = std::fs::read_dir(entry.path()) {
-          for session_entry in session_entries.filter_map(|e| e.ok()) {
-            if session_entry.path().is_dir() {
-              let session_info_path = session_entry
-                .path()
-                .join("session_info.json");
-
-              if session_info_path.exists() {
-                if let Ok(file) = File::open(&session_info_path) {
-                  if
-                    let Ok(session_info) = serde_json::from_reader::<
-                      _,
-                      SessionInfo
-                    >(file)
-                  {
-                    if
-                      session_info.mod_id == mod_id &&
-                      session_info.session_id == session_id
-                    {
-                      return Ok(session_info);
-                    }
-                  }
-                }
-              }
-            }
-          }
-        }
+/// Delete a saved session's folder, and the mod's folder if it was the last one
+pub fn delete_session_folder(mod_id: &str, session_id: &str) -> Result<(), String> {
+  if let Some(session_dir) = get_session_dir(mod_id, session_id) {
+    std::fs
+      ::remove_dir_all(&session_dir)
+      .map_err(|e| format!("Failed to delete session directory: {}", e))?;
+    debug!("Deleted session directory {}", session_dir.display());
+  }
+
+  if crate::sessionindex::remove_session(mod_id, session_id) == 0 {
+    if let Some(mod_dir) = get_mod_logs_dir(mod_id) {
+      if let Err(e) = std::fs::remove_dir_all(&mod_dir) {
+        warn!("Failed to delete empty mod directory {}: {}", mod_dir.display(), e);
       }
     }
   }
-
-  Err("Session info not found".into())
+  Ok(())
 }
 
 /// Save logs to disk for a specific mod session
@@ -372,7 +589,7 @@ fn load_session_info_from_disk(
 fn save_session_logs_to_disk(
   mod_id: &str,
   session_id: &str,
-  logs_to_save: &[String]
+  logs_to_save: &[LogRecord]
 ) -> std::io::Result<()> {
   // Load session info to get start_time and mod_name for path generation
   let session_info = load_session_info_from_disk(mod_id, session_id).map_err(
@@ -393,7 +610,7 @@ fn save_session_logs_to_disk(
   let start_time = session_info.start_time;
   let mod_name = &session_info.mod_name;
 
-  if let Err(e) = ensure_session_logs_dir(mod_name, session_id, start_time) {
+  if let Err(e) = ensure_session_logs_dir(mod_id, session_id, start_time) {
     error!(
       "Failed to create session logs directory for mod {}: {}",
       mod_name,
@@ -402,16 +619,29 @@ fn save_session_logs_to_disk(
     return Err(e);
   }
 
-  let file_path = get_session_log_file_path(mod_name, session_id, start_time);
+  let file_path = get_session_log_file_path(mod_id, session_id, start_time);
   let file = OpenOptions::new().create(true).append(true).open(&file_path)?;
 
   let mut writer = BufWriter::new(file);
 
   for log in logs_to_save {
-    writeln!(writer, "{}", log)?;
+    writeln!(writer, "{}", log.to_log_line())?;
   }
 
   writer.flush()?;
+
+  // Keep the structured records next to the plain output
+  let records_path = get_session_records_file_path(&file_path);
+  let records_file = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(&records_path)?;
+  let mut records_writer = BufWriter::new(records_file);
+  for log in logs_to_save {
+    serde_json::to_writer(&mut records_writer, log)?;
+    writeln!(records_writer)?;
+  }
+  records_writer.flush()?;
   debug!(
     "Saved {} logs to disk for mod {} session {} at {}",
     logs_to_save.len(),
@@ -423,59 +653,67 @@ fn save_session_logs_to_disk(
 }
 
 /// Load all logs from disk for a specific session
-fn load_session_logs_from_disk(
-  mod_name: &str,
-  session_id: &str
-) -> Vec<String> {
-  // Load session info to get start_time for path generation
-  let start_time = if
-    let Ok(session_info) = load_session_info_from_disk(mod_name, session_id)
-  {
-    session_info.start_time
-  } else {
-    // If we can't load session info, try to find the session folder by searching
-    warn!(
-      "Could not load session info for {}/{}, trying to find session folder",
-      mod_name,
-      session_id
-    );
-    return Vec::new(); // Return empty if we can't find session info
-  };
+pub fn load_session_logs_from_disk(session_info: &SessionInfo) -> Vec<LogRecord> {
+  let all_logs = iter_session_logs_from_disk(session_info).collect::<Vec<_>>();
+  debug!(
+    "Loaded {} logs from disk for session {} of mod {}",
+    all_logs.len(),
+    session_info.session_id,
+    session_info.mod_name
+  );
+  all_logs
+}
 
+/// Read the logs of a session from disk one at a time, without loading them all
+pub fn iter_session_logs_from_disk(
+  session_info: &SessionInfo
+) -> Box<dyn Iterator<Item = LogRecord>> {
   let session_log_file = get_session_log_file_path(
-    mod_name,
-    session_id,
-    start_time
+    &session_info.mod_id,
+    &session_info.session_id,
+    session_info.start_time
   );
 
   if !Path::new(&session_log_file).exists() {
-    return Vec::new();
+    return Box::new(std::iter::empty());
   }
 
-  let mut all_logs = Vec::new();
+  // Sessions saved by older versions only have the plain output
+  let records_file = get_session_records_file_path(&session_log_file);
+  if records_file.exists() {
+    match File::open(&records_file) {
+      Ok(file) => {
+        return Box::new(
+          BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<LogRecord>(&line).ok())
+        );
+      }
+      Err(e) => {
+        warn!(
+          "Failed to read session records file {}, falling back to the plain log: {}",
+          records_file.display(),
+          e
+        );
+      }
+    }
+  }
 
   match File::open(&session_log_file) {
     Ok(file) => {
-      let reader = BufReader::new(file);
-      let file_logs: Vec<String> = reader
-        .lines()
-        .filter_map(|line| line.ok())
-        .collect();
-      all_logs.extend(file_logs);
-
-      debug!(
-        "Loaded {} logs from disk for session {} of mod {}",
-        all_logs.len(),
-        session_id,
-        mod_name
-      );
+      Box::new(
+        BufReader::new(file)
+          .lines()
+          .map_while(Result::ok)
+          .map(|line| crate::logparser::parse_saved_line(&line))
+      )
     }
     Err(e) => {
       error!("Failed to read session log file {}: {}", session_log_file, e);
+      Box::new(std::iter::empty())
     }
   }
-
-  all_logs
 }
 
 /// Add a log entry to a specific mod's current session
@@ -494,9 +732,29 @@ pub fn add_log(mod_id: &str, log_entry: &str) {
   add_session_log(mod_id, &session_id, log_entry);
 }
 
+/// Add a line printed by a running mod to its session
+pub fn add_output_log(
+  mod_id: &str,
+  session_id: &str,
+  stream: LogStream,
+  line: &str
+) {
+  let record = crate::logparser::parse_output_line(stream, line);
+  add_session_record(mod_id, session_id, record);
+}
+
 /// Add a log entry to a specific mod session
 pub fn add_session_log(mod_id: &str, session_id: &str, log_entry: &str) {
   debug!("Adding log for mod {} session {}: {}", mod_id, session_id, log_entry);
+  add_session_record(
+    mod_id,
+    session_id,
+    crate::logparser::system_record(log_entry)
+  );
+}
+
+fn add_session_record(mod_id: &str, session_id: &str, log_entry: LogRecord) {
+  let should_save_output = session_saves_output(mod_id, session_id);
   let mut logs = SESSION_LOGS.lock().unwrap();
 
   // Ensure mod entry exists
@@ -507,16 +765,38 @@ pub fn add_session_log(mod_id: &str, session_id: &str, log_entry: &str) {
   // Ensure session entry exists
   let session_logs = mod_sessions
     .entry(session_id.to_string())
-    .or_insert_with(Vec::new);
-
-  // Add the log entry
-  session_logs.push(log_entry.to_string());
+    .or_insert_with(VecDeque::new);
+
+  // Add the log entry and send it to the frontend
+  let evicted_count = EVICTED_LOG_COUNTS.lock()
+    .unwrap()
+    .get(session_id)
+    .copied()
+    .unwrap_or(0);
+  crate::logstream::queue_line(
+    mod_id,
+    session_id,
+    evicted_count + session_logs.len(),
+    &log_entry
+  );
+  session_logs.push_back(log_entry);
+
+  // Mods that don't save their output only keep the newest lines
+  if !should_save_output {
+    let max_logs = crate::logretention::get_max_unsaved_logs_in_memory();
+    if session_logs.len() > max_logs {
+      let dropped = session_logs.len() - max_logs;
+      session_logs.drain(0..dropped);
+      add_evicted_count(session_id, dropped);
+      *TRUNCATED_LOG_COUNTS.lock()
+        .unwrap()
+        .entry(session_id.to_string())
+        .or_insert(0) += dropped;
+    }
+  }
 
   // If the log count exceeds the memory limit AND mod wants to save output, save older logs to disk
-  if
-    session_logs.len() > MAX_LOGS_IN_MEMORY &&
-    get_mod_save_terminal_output_setting(mod_id)
-  {
+  if session_logs.len() > MAX_LOGS_IN_MEMORY && should_save_output {
     debug!(
       "Session {} for mod {} has too many log entries in memory, archiving oldest to disk",
       session_id,
@@ -525,7 +805,7 @@ pub fn add_session_log(mod_id: &str, session_id: &str, log_entry: &str) {
 
     // Calculate how many logs to move to disk
     let logs_to_archive = MAX_LOGS_IN_MEMORY / 2; // Only archive half of the logs at a time
-    let logs_to_save: Vec<String> = session_logs
+    let logs_to_save: Vec<LogRecord> = session_logs
       .drain(0..logs_to_archive)
       .collect();
 
@@ -544,6 +824,8 @@ pub fn add_session_log(mod_id: &str, session_id: &str, log_entry: &str) {
         for (i, log) in logs_to_save.into_iter().enumerate() {
           session_logs.insert(i, log);
         }
+      } else {
+        add_evicted_count(session_id, logs_to_save.len());
       }
     } else {
       // If we can't get session info, put the logs back
@@ -565,34 +847,71 @@ pub fn add_session_log(mod_id: &str, session_id: &str, log_entry: &str) {
 
 /// Get all logs for a specific mod's current session (both in-memory and from disk)
 pub fn get_logs(mod_id: &str) -> Vec<String> {
+  get_current_session_logs(mod_id).lines
+}
+
+/// Get all logs for a specific mod's current session and how many were truncated
+pub fn get_current_session_logs(mod_id: &str) -> SessionLogs {
   // Get active session
   let session_id = match get_active_session(mod_id) {
     Some(id) => id,
     None => {
       debug!("No active session for mod {}", mod_id);
-      return Vec::new();
+      return SessionLogs::default();
     }
   };
 
   get_session_logs(mod_id, &session_id)
 }
 
-/// Get all logs for a specific session (both in-memory and from disk)
-pub fn get_session_logs(mod_id: &str, session_id: &str) -> Vec<String> {
+/// Get all logs for a specific session (both in-memory and from disk) and how
+/// many lines were dropped before they could be saved
+pub fn get_session_logs(mod_id: &str, session_id: &str) -> SessionLogs {
+  SessionLogs {
+    lines: get_session_records(mod_id, session_id)
+      .iter()
+      .map(|record| record.to_log_line())
+      .collect(),
+    truncated_lines: get_truncated_log_count(session_id),
+  }
+}
+
+/// Get how many lines of a session were dropped from the in-memory buffer
+pub fn get_truncated_log_count(session_id: &str) -> usize {
+  TRUNCATED_LOG_COUNTS.lock()
+    .unwrap()
+    .get(session_id)
+    .copied()
+    .unwrap_or(0)
+}
+
+/// Get all log records for a specific mod's current session
+pub fn get_records(mod_id: &str) -> Vec<LogRecord> {
+  match get_active_session(mod_id) {
+    Some(session_id) => get_session_records(mod_id, &session_id),
+    None => {
+      debug!("No active session for mod {}", mod_id);
+      Vec::new()
+    }
+  }
+}
+
+/// Get all log records for a specific session (both in-memory and from disk)
+pub fn get_session_records(mod_id: &str, session_id: &str) -> Vec<LogRecord> {
   let logs = SESSION_LOGS.lock().unwrap();
 
   // Get in-memory logs
   let memory_logs = logs
     .get(mod_id)
     .and_then(|sessions| sessions.get(session_id))
-    .cloned()
+    .map(|records| records.iter().cloned().collect::<Vec<_>>())
     .unwrap_or_else(Vec::new);
 
   // Get logs from disk - we need mod name for this
   let disk_logs = if
     let Ok(session_info) = load_session_info_from_disk(mod_id, session_id)
   {
-    load_session_logs_from_disk(&session_info.mod_name, session_id)
+    load_session_logs_from_disk(&session_info)
   } else {
     Vec::new()
   };
@@ -613,6 +932,88 @@ pub fn get_session_logs(mod_id: &str, session_id: &str) -> Vec<String> {
   all_logs
 }
 
+/// Get the log records of a session from an offset on. Only reads from disk
+/// if some of the requested logs aren't in memory anymore
+pub fn get_session_records_since(
+  mod_id: &str,
+  session_id: &str,
+  offset: usize
+) -> SessionLogsSince {
+  let logs = SESSION_LOGS.lock().unwrap();
+  let memory_logs = logs.get(mod_id).and_then(|sessions| sessions.get(session_id));
+  let evicted_count = EVICTED_LOG_COUNTS.lock()
+    .unwrap()
+    .get(session_id)
+    .copied()
+    .unwrap_or(0);
+
+  let truncated_lines = get_truncated_log_count(session_id);
+
+  if let Some(memory_logs) = memory_logs {
+    if offset >= evicted_count {
+      let start = (offset - evicted_count).min(memory_logs.len());
+      return SessionLogsSince {
+        records: memory_logs.range(start..).cloned().collect(),
+        next_offset: evicted_count + memory_logs.len(),
+        truncated_lines,
+      };
+    }
+  }
+
+  // Some of the requested logs were already moved to disk, or dropped
+  let mut all_logs = match load_session_info_from_disk(mod_id, session_id) {
+    Ok(session_info) => {
+      load_session_logs_from_disk(&session_info)
+    }
+    Err(_) => Vec::new(),
+  };
+  if let Some(memory_logs) = memory_logs {
+    all_logs.extend(memory_logs.iter().cloned());
+  }
+
+  // Dropped lines are gone, so the first available line can be past offset 0
+  let next_offset = match memory_logs {
+    Some(memory_logs) => evicted_count + memory_logs.len(),
+    None => all_logs.len(),
+  };
+  let first_offset = next_offset.saturating_sub(all_logs.len());
+  SessionLogsSince {
+    records: all_logs
+      .into_iter()
+      .skip(offset.saturating_sub(first_offset))
+      .collect(),
+    next_offset,
+    truncated_lines,
+  }
+}
+
+/// Get the log records of a session that are still in memory
+pub fn get_memory_records(mod_id: &str, session_id: &str) -> Vec<LogRecord> {
+  SESSION_LOGS.lock()
+    .unwrap()
+    .get(mod_id)
+    .and_then(|sessions| sessions.get(session_id))
+    .map(|records| records.iter().cloned().collect())
+    .unwrap_or_default()
+}
+
+/// Get the info of every session that hasn't ended yet
+pub fn get_running_session_infos() -> Vec<SessionInfo> {
+  SESSION_INFOS.lock().unwrap().values().cloned().collect()
+}
+
+/// How many logs of a running session were moved to disk or dropped from memory
+pub fn get_evicted_log_count(session_id: &str) -> usize {
+  EVICTED_LOG_COUNTS.lock().unwrap().get(session_id).copied().unwrap_or(0)
+}
+
+fn add_evicted_count(session_id: &str, count: usize) {
+  *EVICTED_LOG_COUNTS.lock()
+    .unwrap()
+    .entry(session_id.to_string())
+    .or_insert(0) += count;
+}
+
 /// Get only the logs currently in memory for the current session of a mod
 pub fn get_memory_logs(mod_id: &str) -> Vec<String> {
   let session_id = match get_active_session(mod_id) {
@@ -626,7 +1027,7 @@ pub fn get_memory_logs(mod_id: &str) -> Vec<String> {
   logs
     .get(mod_id)
     .and_then(|sessions| sessions.get(&session_id))
-    .cloned()
+    .map(|records| records.iter().map(|r| r.to_log_line()).collect())
     .unwrap_or_else(Vec::new)
 }
 
@@ -647,76 +1048,22 @@ pub fn clear_logs(mod_id: &str) {
     active_sessions.remove(mod_id);
   }
 
-  // Clear disk logs by finding and deleting all mod directories
-  let logs_dir = match &*LOGS_DIR {
-    Ok(path_buf) => path_buf,
-    Err(e) => {
-      error!("Failed to resolve LOGS_DIR: {}", e);
+  // Clear disk logs, all of the mod's sessions are in its own folder
+  let mod_dir = match get_mod_logs_dir(mod_id) {
+    Some(mod_dir) => mod_dir,
+    None => {
       return;
     }
   };
-
-  // Search for directories containing sessions for this mod
-  if let Ok(entries) = std::fs::read_dir(logs_dir) {
-    for entry in entries.filter_map(|e| e.ok()) {
-      if entry.path().is_dir() {
-        // Check each session directory for this mod
-        if let Ok(session_entries) = std::fs::read_dir(entry.path()) {
-          for session_entry in session_entries.filter_map(|e| e.ok()) {
-            if session_entry.path().is_dir() {
-              let session_info_path = session_entry
-                .path()
-                .join("session_info.json");
-              if session_info_path.exists() {
-                if let Ok(file) = File::open(&session_info_path) {
-                  if
-                    let Ok(session_info) = serde_json::from_reader::<
-                      _,
-                      SessionInfo
-                    >(file)
-                  {
-                    if session_info.mod_id == mod_id {
-                      // This session belongs to our mod, remove the entire session directory
-                      if
-                        let Err(e) = std::fs::remove_dir_all(
-                          session_entry.path()
-                        )
-                      {
-                        error!(
-                          "Failed to delete session directory {:?}: {}",
-                          session_entry.path(),
-                          e
-                        );
-                      } else {
-                        debug!(
-                          "Deleted session directory for mod {}: {:?}",
-                          mod_id,
-                          session_entry.path()
-                        );
-                      }
-                    }
-                  }
-                }
-              }
-            }
-          }
-        }
-
-        // If the mod directory is now empty, remove it
-        if let Ok(mut mod_entries) = std::fs::read_dir(entry.path()) {
-          if mod_entries.next().is_none() {
-            if let Err(e) = std::fs::remove_dir(entry.path()) {
-              error!(
-                "Failed to delete empty mod directory {:?}: {}",
-                entry.path(),
-                e
-              );
-            }
-          }
-        }
+  if mod_dir.exists() {
+    match std::fs::remove_dir_all(&mod_dir) {
+      Ok(_) => debug!("Deleted session directories for mod {}", mod_id),
+      Err(e) => {
+        error!("Failed to delete session directory {:?}: {}", mod_dir, e);
       }
     }
   }
+  crate::sessionindex::remove_mod_sessions(mod_id);
 }
 
 /// Clear all logs for all mods (both in-memory and on disk)
@@ -756,6 +1103,7 @@ pub fn clear_all_logs() {
       Err(e) => error!("Failed to delete logs directory: {}", e),
     }
   }
+  crate::sessionindex::clear_index();
 }
 
 /// Clear only in-memory logs for the current session (save them to disk first)
@@ -773,7 +1121,7 @@ pub fn clear_memory_logs(mod_id: &str) {
   let mut logs = SESSION_LOGS.lock().unwrap();
 
   if let Some(mod_sessions) = logs.get_mut(mod_id) {
-    if let Some(session_logs) = mod_sessions.get(session_id.as_str()) {
+    if let Some(session_logs) = mod_sessions.get_mut(session_id.as_str()) {
       // Only save if there are logs in memory to save
       if !session_logs.is_empty() {
         // Save all current in-memory logs to disk
@@ -781,7 +1129,7 @@ pub fn clear_memory_logs(mod_id: &str) {
           let Err(e) = save_session_logs_to_disk(
             mod_id,
             &session_id,
-            session_logs
+            session_logs.make_contiguous()
           )
         {
           error!(
@@ -799,10 +1147,11 @@ pub fn clear_memory_logs(mod_id: &str) {
           mod_id,
           session_id
         );
+        add_evicted_count(&session_id, session_logs.len());
       }
 
       // Clear the in-memory logs for this session
-      mod_sessions.insert(session_id.clone(), Vec::new());
+      mod_sessions.insert(session_id.clone(), VecDeque::new());
       debug!(
         "In-memory logs cleared for mod {} session {}",
         mod_id,
@@ -826,15 +1175,15 @@ pub fn clear_all_memory_logs() {
   let mut logs = SESSION_LOGS.lock().unwrap();
 
   // Save all in-memory logs to disk before clearing
-  for (mod_id, mod_sessions) in logs.iter() {
-    for (session_id, session_logs) in mod_sessions.iter() {
+  for (mod_id, mod_sessions) in logs.iter_mut() {
+    for (session_id, session_logs) in mod_sessions.iter_mut() {
       if !session_logs.is_empty() {
         // Save logs to disk
         if
           let Err(e) = save_session_logs_to_disk(
             mod_id,
             session_id,
-            session_logs
+            session_logs.make_contiguous()
           )
         {
           error!(
@@ -851,6 +1200,8 @@ pub fn clear_all_memory_logs() {
             session_id
           );
         }
+        // Count them even if saving failed, they're gone from memory either way
+        add_evicted_count(session_id, session_logs.len());
       }
     }
   }
@@ -862,42 +1213,7 @@ pub fn clear_all_memory_logs() {
 
 /// Get the logs folder path for a specific mod
 pub fn get_mod_logs_folder_path(mod_id: &str) -> Result<String, String> {
-  // Try to get mod info to get the mod name
-  let mod_name = if
-    let Ok(global_mods) = crate::models::GLOBAL_MODS_STATE.lock()
-  {
-    if let Some(mod_info) = global_mods.get(mod_id) {
-      mod_info.name.clone()
-    } else {
-      return Err(format!("Mod with ID {} not found", mod_id));
-    }
-  } else if let Some(app_handle) = crate::app_handle::get_global_app_handle() {
-    if
-      let Some(mods_state) = app_handle.try_state::<crate::models::ModsState>()
-    {
-      if let Ok(mods) = mods_state.0.lock() {
-        if let Some(mod_info) = mods.get(mod_id) {
-          mod_info.name.clone()
-        } else {
-          return Err(format!("Mod with ID {} not found", mod_id));
-        }
-      } else {
-        return Err("Failed to access mods state".to_string());
-      }
-    } else {
-      return Err("Failed to get mods state".to_string());
-    }
-  } else {
-    return Err("Failed to access mod information".to_string());
-  };
-
-  let sanitized_name = sanitize_mod_name_for_filesystem(&mod_name);
-  let logs_dir = match &*LOGS_DIR {
-    Ok(path_buf) => path_buf.join(&sanitized_name),
-    Err(e) => {
-      return Err(format!("Failed to resolve logs directory: {}", e));
-    }
-  };
-
-  Ok(logs_dir.display().to_string())
+  get_mod_logs_dir(mod_id)
+    .map(|logs_dir| logs_dir.display().to_string())
+    .ok_or_else(|| "Failed to resolve logs directory".to_string())
 }
@@ -1,8 +1,10 @@
 use base64::{ engine::general_purpose::STANDARD as BASE64, Engine as _ };
 use log::{ debug, error };
 use reqwest;
-use std::path::Path;
+use serde::{ de::DeserializeOwned, Serialize };
+use std::path::{ Path, PathBuf };
 use image::{ ImageBuffer, Rgba };
+use tauri::Manager;
 
 // Function to fetch and convert an image to base64 data URL
 pub async fn fetch_image_as_base64(url: &str) -> Option<String> {
@@ -131,13 +133,72 @@ pub async fn fetch_image_as_data_url(url: &str) -> Option<String> {
   Some(format!("data:{};base64,{}", content_type, b64))
 }
 
+/// Resolve a file inside the app data directory
+pub fn get_app_data_file(file_name: &str) -> Result<PathBuf, String> {
+  let app_handle = crate::app_handle
+    ::get_global_app_handle()
+    .ok_or_else(|| "App handle not available".to_string())?;
+
+  app_handle
+    .path()
+    .app_data_dir()
+    .map(|dir| dir.join(file_name))
+    .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+/// Read a JSON file, falling back to the default value if it's missing or invalid
+pub fn read_json_file<T: DeserializeOwned + Default>(path: &Path) -> T {
+  if !path.exists() {
+    return T::default();
+  }
+
+  match std::fs::read_to_string(path) {
+    Ok(content) =>
+      match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+          error!("Failed to parse {}: {}", path.display(), e);
+          T::default()
+        }
+      }
+    Err(e) => {
+      error!("Failed to read {}: {}", path.display(), e);
+      T::default()
+    }
+  }
+}
+
+/// Write a value to a JSON file, creating the parent directory if needed
+pub fn write_json_file<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+  if let Some(parent) = path.parent() {
+    std::fs
+      ::create_dir_all(parent)
+      .map_err(|e|
+        format!("Failed to create directory {}: {}", parent.display(), e)
+      )?;
+  }
+
+  let json_string = serde_json
+    ::to_string_pretty(value)
+    .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+
+  std::fs
+    ::write(path, json_string)
+    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
 // Function to extract RAR archives
 pub fn extract_rar_archive(
   mut archive: unrar::OpenArchive<unrar::Process, unrar::CursorBeforeHeader>,
-  extraction_path: &str
+  extraction_path: &str,
+  cancel_token: &crate::downloadqueue::CancelToken
 ) -> Result<(), String> {
   // Create a loop to read headers and process files
   loop {
+    if cancel_token.is_cancelled() {
+      return Err(crate::downloadqueue::DOWNLOAD_CANCELLED.to_string());
+    }
+
     // Try to read the next header
     let archive_with_file = match archive.read_header() {
       Ok(Some(a)) => a,
@@ -327,6 +327,46 @@ export class NotificationService {
     })
   }
 
+  /**
+   * Show a notification for a launch waiting for another mod to exit
+   */
+  public modLaunchQueued(modName: string, runningModName: string): void {
+    this.info({
+      message: getT()('app.notifications.mod.queued', {
+        modName,
+        runningModName,
+      }),
+      timeout: 3000,
+      position: 'bottom-right',
+    })
+  }
+
+  /**
+   * Show a notification for a launch blocked by another running mod
+   */
+  public modLaunchConflict(
+    modName: string,
+    runningModName: string,
+    error: string,
+    onQueue: () => void
+  ): void {
+    this.warning({
+      message: getT()('app.notifications.mod.conflict', {
+        modName,
+        runningModName,
+      }),
+      caption: error,
+      timeout: 5000,
+      position: 'bottom-right',
+      actions: [
+        {
+          label: getT()('app.notifications.mod.launch_when_exits'),
+          handler: onQueue,
+        },
+      ],
+    })
+  }
+
   /**
    * Show a mod error notification
    */
@@ -72,6 +72,31 @@ export interface ModInfoGBData {
   model_type: string
 }
 
+/**
+ * Result of launching a mod, a launch can wait for a conflicting mod to exit
+ */
+export interface ModLaunchResult {
+  launched: boolean
+  queued_behind: string | null // ID of the running mod this launch waits for
+}
+
+/**
+ * A running mod sharing an engine install with the one being launched
+ */
+export interface LaunchConflict {
+  conflicting_mod_id: string
+  conflicting_mod_name: string
+  reason: 'shared_executable_directory' | 'shared_mods_folder'
+  shared_path: string
+}
+
+/**
+ * Error returned when launching a mod fails
+ */
+export type LaunchError =
+  | { kind: 'conflict'; message: string; conflict: LaunchConflict }
+  | { kind: 'failed'; message: string }
+
 /**
  * Represents a mod profile for engine mods
  */